@@ -0,0 +1,105 @@
+use std::f32::consts::PI;
+
+pub const MAX_HARMONICS: usize = 32;
+pub const CUSTOM_HARMONICS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HarmonicProfile {
+    Custom,
+    Saw,
+    Square,
+    Organ,
+    Bell,
+}
+
+impl HarmonicProfile {
+    const ALL: [HarmonicProfile; 5] = [
+        HarmonicProfile::Custom,
+        HarmonicProfile::Saw,
+        HarmonicProfile::Square,
+        HarmonicProfile::Organ,
+        HarmonicProfile::Bell,
+    ];
+
+    pub fn from_param(value: f32) -> HarmonicProfile {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HarmonicProfile::Custom => "Custom",
+            HarmonicProfile::Saw => "Saw",
+            HarmonicProfile::Square => "Square",
+            HarmonicProfile::Organ => "Organ",
+            HarmonicProfile::Bell => "Bell",
+        }
+    }
+
+    // Amplitude of harmonic `k` (1-based); `custom` holds the user levels for the
+    // Custom profile.
+    fn amplitude(&self, k: usize, custom: &[f32]) -> f32 {
+        match self {
+            HarmonicProfile::Custom => custom.get(k - 1).copied().unwrap_or(0.0),
+            HarmonicProfile::Saw => 1.0 / k as f32,
+            HarmonicProfile::Square => {
+                if k % 2 == 1 {
+                    1.0 / k as f32
+                } else {
+                    0.0
+                }
+            }
+            HarmonicProfile::Organ => match k {
+                1 | 2 | 4 | 8 | 16 => 1.0,
+                3 | 6 | 12 => 0.5,
+                _ => 0.0,
+            },
+            HarmonicProfile::Bell => match k {
+                1 => 1.0,
+                2 => 0.2,
+                3 => 0.7,
+                5 => 0.45,
+                7 => 0.35,
+                9 | 11 => 0.25,
+                _ => 0.1 / k as f32,
+            },
+        }
+    }
+
+    pub fn amplitudes(&self, count: usize, custom: &[f32]) -> [f32; MAX_HARMONICS] {
+        let mut amplitudes = [0.0; MAX_HARMONICS];
+        for (i, amplitude) in amplitudes.iter_mut().take(count).enumerate() {
+            *amplitude = self.amplitude(i + 1, custom);
+        }
+        amplitudes
+    }
+}
+
+// Sums sin(2πk·phase) weighted by `amplitudes[k - 1]`, dropping any harmonic that would
+// land above Nyquist. The sines are produced by the Chebyshev recurrence
+// sin((k+1)x) = 2cos(x)·sin(kx) − sin((k−1)x), so only one sin/cos pair is evaluated.
+pub fn render(phase: f32, phase_inc: f32, amplitudes: &[f32]) -> f32 {
+    let x = phase * 2.0 * PI;
+    let two_cos = 2.0 * x.cos();
+    let (mut previous, mut current) = (0.0, x.sin());
+    let mut sum = 0.0;
+    let mut total = 0.0;
+
+    for (i, &amplitude) in amplitudes.iter().enumerate() {
+        if (i + 1) as f32 * phase_inc >= 0.5 {
+            break;
+        }
+        sum += amplitude * current;
+        total += amplitude.abs();
+
+        let next = two_cos * current - previous;
+        previous = current;
+        current = next;
+    }
+
+    if total > 0.0 {
+        sum / total
+    } else {
+        0.0
+    }
+}
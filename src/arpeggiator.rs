@@ -0,0 +1,161 @@
+// Plays the held keys one at a time, clocked from the host tempo, instead of as a chord.
+
+use crate::random::Random;
+
+pub const ARP_NOTES: usize = 32;
+pub const MAX_OCTAVES: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ArpMode {
+    Up,
+    Down,
+    // Up then back down, without repeating the top and bottom notes.
+    UpDown,
+    Random,
+}
+
+impl ArpMode {
+    const ALL: [ArpMode; 4] = [ArpMode::Up, ArpMode::Down, ArpMode::UpDown, ArpMode::Random];
+
+    pub fn from_param(value: f32) -> ArpMode {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArpMode::Up => "Up",
+            ArpMode::Down => "Down",
+            ArpMode::UpDown => "Up/Down",
+            ArpMode::Random => "Random",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ArpNote {
+    pub channel: u8,
+    pub note: u8,
+    pub velocity: u8,
+}
+
+// What happens on one sample: the note that was playing ends, the next one starts, or
+// both. The note-off comes first.
+#[derive(Default)]
+pub struct ArpStep {
+    pub off: Option<ArpNote>,
+    pub on: Option<ArpNote>,
+}
+
+pub struct Arpeggiator {
+    // Keys down, lowest first.
+    held: [ArpNote; ARP_NOTES],
+    len: usize,
+    step: usize,
+    // Progress through the current step; at 1 the next one is due.
+    phase: f32,
+    playing: Option<ArpNote>,
+    rng: Random,
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Arpeggiator {
+        Arpeggiator {
+            held: [ArpNote::default(); ARP_NOTES],
+            len: 0,
+            step: 0,
+            phase: 1.0,
+            playing: None,
+            rng: Random::default(),
+        }
+    }
+}
+
+impl Arpeggiator {
+    pub fn press(&mut self, channel: u8, note: u8, velocity: u8) {
+        self.release(channel, note);
+        if self.len == ARP_NOTES {
+            return;
+        }
+        // The first key starts the pattern from the top, straight away.
+        if self.len == 0 {
+            self.step = 0;
+            self.phase = 1.0;
+        }
+        let held = &self.held[..self.len];
+        let index = held.iter().position(|h| h.note > note).unwrap_or(self.len);
+        self.held.copy_within(index..self.len, index + 1);
+        self.held[index] = ArpNote { channel, note, velocity };
+        self.len += 1;
+    }
+
+    pub fn release(&mut self, channel: u8, note: u8) {
+        let held = &self.held[..self.len];
+        if let Some(index) = held.iter().position(|h| h.channel == channel && h.note == note) {
+            self.held.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+
+    // Goes back to the first step, which plays at once if keys are down.
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.phase = 1.0;
+    }
+
+    // Forgets every key and returns the note left sounding, which needs a note-off.
+    pub fn stop(&mut self) -> Option<ArpNote> {
+        self.len = 0;
+        self.playing.take()
+    }
+
+    // Advances by one sample. `gate` is the fraction of each step a note is held for.
+    pub fn next(
+        &mut self,
+        mode: ArpMode,
+        octaves: usize,
+        rate_hz: f32,
+        gate: f32,
+        per_sample: f32,
+    ) -> ArpStep {
+        let mut step = ArpStep::default();
+        if self.len == 0 {
+            step.off = self.playing.take();
+            return step;
+        }
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            step.off = self.playing.take();
+            self.playing = self.note_at(mode, octaves.clamp(1, MAX_OCTAVES));
+            step.on = self.playing;
+            self.step = self.step.wrapping_add(1);
+        } else if self.phase >= gate {
+            step.off = self.playing.take();
+        }
+        self.phase += rate_hz * per_sample;
+        step
+    }
+
+    // The pattern runs through every held key in each octave in turn. Notes pushed past
+    // the top of the MIDI range are rests.
+    fn note_at(&mut self, mode: ArpMode, octaves: usize) -> Option<ArpNote> {
+        let len = self.len * octaves;
+        let index = match mode {
+            ArpMode::Up => self.step % len,
+            ArpMode::Down => len - 1 - self.step % len,
+            ArpMode::UpDown if len > 1 => {
+                let period = 2 * len - 2;
+                let position = self.step % period;
+                position.min(period - position)
+            }
+            ArpMode::UpDown => 0,
+            ArpMode::Random => ((self.rng.unit() * len as f32) as usize).min(len - 1),
+        };
+        let held = self.held[index % self.len];
+        let note = held.note as usize + 12 * (index / self.len);
+        (note <= 127).then_some(ArpNote {
+            note: note as u8,
+            ..held
+        })
+    }
+}
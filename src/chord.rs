@@ -0,0 +1,159 @@
+const MAX_KEYS: usize = 32;
+
+// Semitones above the root, one bit each; bit 0 is the root itself.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Shape(pub u32);
+
+impl Shape {
+    pub const ROOT: Shape = Shape(1);
+    pub const MAJOR: Shape = Shape::of(&[0, 4, 7]);
+
+    const fn of(intervals: &[u32]) -> Shape {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < intervals.len() {
+            bits |= 1 << intervals[i];
+            i += 1;
+        }
+        Shape(bits)
+    }
+
+    // The chord built on `root`, lowest first, leaving out anything above the MIDI range.
+    pub fn notes(self, root: u8) -> impl Iterator<Item = u8> {
+        (0..32)
+            .filter(move |interval| self.0 >> interval & 1 != 0)
+            .map(move |interval| root as u32 + interval)
+            .take_while(|&note| note <= 127)
+            .map(|note| note as u8)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChordMode {
+    Off,
+    // The last chord played while Chord Learn was on.
+    Learned,
+    Major,
+    Minor,
+    Seventh,
+    MajorSeventh,
+    MinorSeventh,
+    Sus4,
+    Fifth,
+    Octave,
+}
+
+impl ChordMode {
+    const ALL: [ChordMode; 10] = [
+        ChordMode::Off,
+        ChordMode::Learned,
+        ChordMode::Major,
+        ChordMode::Minor,
+        ChordMode::Seventh,
+        ChordMode::MajorSeventh,
+        ChordMode::MinorSeventh,
+        ChordMode::Sus4,
+        ChordMode::Fifth,
+        ChordMode::Octave,
+    ];
+
+    pub fn from_param(value: f32) -> ChordMode {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChordMode::Off => "Off",
+            ChordMode::Learned => "Learned",
+            ChordMode::Major => "Major",
+            ChordMode::Minor => "Minor",
+            ChordMode::Seventh => "7th",
+            ChordMode::MajorSeventh => "Maj7",
+            ChordMode::MinorSeventh => "Min7",
+            ChordMode::Sus4 => "Sus4",
+            ChordMode::Fifth => "5th",
+            ChordMode::Octave => "Octave",
+        }
+    }
+
+    // `None` for `Learned`, whose shape lives with the parameters.
+    pub fn shape(&self) -> Option<Shape> {
+        match self {
+            ChordMode::Off => Some(Shape::ROOT),
+            ChordMode::Learned => None,
+            ChordMode::Major => Some(Shape::MAJOR),
+            ChordMode::Minor => Some(Shape::of(&[0, 3, 7])),
+            ChordMode::Seventh => Some(Shape::of(&[0, 4, 7, 10])),
+            ChordMode::MajorSeventh => Some(Shape::of(&[0, 4, 7, 11])),
+            ChordMode::MinorSeventh => Some(Shape::of(&[0, 3, 7, 10])),
+            ChordMode::Sus4 => Some(Shape::of(&[0, 5, 7])),
+            ChordMode::Fifth => Some(Shape::of(&[0, 7])),
+            ChordMode::Octave => Some(Shape::of(&[0, 12])),
+        }
+    }
+}
+
+// Remembers the shape each held key started, so its release stops the same notes even
+// if the chord has been changed in between.
+pub struct ChordMemory {
+    keys: [(u8, u8, Shape); MAX_KEYS],
+    len: usize,
+    // Note numbers held while learning, one bit each.
+    learning: u128,
+}
+
+impl Default for ChordMemory {
+    fn default() -> ChordMemory {
+        ChordMemory {
+            keys: [(0, 0, Shape::ROOT); MAX_KEYS],
+            len: 0,
+            learning: 0,
+        }
+    }
+}
+
+impl ChordMemory {
+    pub fn press(&mut self, channel: u8, note: u8, shape: Shape) {
+        self.release(channel, note);
+        if self.len == MAX_KEYS {
+            self.keys.copy_within(1.., 0);
+            self.len -= 1;
+        }
+        self.keys[self.len] = (channel, note, shape);
+        self.len += 1;
+    }
+
+    // The shape `note` was pressed with; just the note, if it wasn't.
+    pub fn release(&mut self, channel: u8, note: u8) -> Shape {
+        let keys = &self.keys[..self.len];
+        match keys.iter().position(|&(c, n, _)| (c, n) == (channel, note)) {
+            Some(index) => {
+                let shape = self.keys[index].2;
+                self.keys.copy_within(index + 1..self.len, index);
+                self.len -= 1;
+                shape
+            }
+            None => Shape::ROOT,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Adds a key to the chord being learned and returns its shape so far, once there is
+    // more than one note. Notes more than 31 semitones above the lowest are left out.
+    pub fn learn(&mut self, note: u8) -> Option<Shape> {
+        self.learning |= 1 << note;
+        if self.learning.count_ones() < 2 {
+            return None;
+        }
+        let lowest = self.learning.trailing_zeros();
+        Some(Shape((self.learning >> lowest) as u32))
+    }
+
+    pub fn unlearn(&mut self, note: u8) {
+        self.learning &= !(1 << note);
+    }
+}
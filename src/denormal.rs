@@ -0,0 +1,90 @@
+// Decaying tails, such as long releases and resonant filters ringing out, sink into
+// denormal floats, which x86 handles in microcode at many times the cost of a normal
+// one. While a `FlushDenormals` is alive the FPU treats them as zero instead, and the
+// host's own setting comes back when it is dropped.
+
+#[cfg(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse")))]
+mod fpu {
+    use std::arch::asm;
+
+    pub type Word = u32;
+
+    // MXCSR flush-to-zero and denormals-are-zero.
+    const FLUSH: u32 = 0x8000 | 0x0040;
+
+    pub fn get() -> u32 {
+        let mut csr = 0u32;
+        // Only stores the SSE control register.
+        unsafe { asm!("stmxcsr [{}]", in(reg) &mut csr, options(nostack, preserves_flags)) };
+        csr
+    }
+
+    pub fn set(csr: u32) {
+        // Only loads the SSE control register, from a value `get` read.
+        unsafe { asm!("ldmxcsr [{}]", in(reg) &csr, options(nostack, readonly)) };
+    }
+
+    pub fn flushing(csr: u32) -> u32 {
+        csr | FLUSH
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod fpu {
+    use std::arch::asm;
+
+    pub type Word = u64;
+
+    // FPCR flush-to-zero, which covers inputs as well as results.
+    const FLUSH: u64 = 1 << 24;
+
+    pub fn get() -> u64 {
+        let fpcr: u64;
+        // Only reads the floating-point control register.
+        unsafe { asm!("mrs {}, fpcr", out(reg) fpcr, options(nomem, nostack)) };
+        fpcr
+    }
+
+    pub fn set(fpcr: u64) {
+        // Only writes the floating-point control register, from a value `get` read.
+        unsafe { asm!("msr fpcr, {}", in(reg) fpcr, options(nomem, nostack)) };
+    }
+
+    pub fn flushing(fpcr: u64) -> u64 {
+        fpcr | FLUSH
+    }
+}
+
+// Elsewhere there's nothing to switch; denormals are left to the hardware.
+#[cfg(not(any(
+    target_arch = "x86_64",
+    all(target_arch = "x86", target_feature = "sse"),
+    target_arch = "aarch64"
+)))]
+mod fpu {
+    pub type Word = ();
+
+    pub fn get() {}
+
+    pub fn set(_: ()) {}
+
+    pub fn flushing(_: ()) {}
+}
+
+pub struct FlushDenormals {
+    saved: fpu::Word,
+}
+
+impl FlushDenormals {
+    pub fn new() -> FlushDenormals {
+        let saved = fpu::get();
+        fpu::set(fpu::flushing(saved));
+        FlushDenormals { saved }
+    }
+}
+
+impl Drop for FlushDenormals {
+    fn drop(&mut self) {
+        fpu::set(self.saved);
+    }
+}
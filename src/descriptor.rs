@@ -0,0 +1,969 @@
+// Everything about each host parameter that isn't how the voices use it: its name, where
+// its value is kept and what it starts at, what range it covers and how the value reads.
+// The table is in index order, so a parameter's entry is found by its index.
+
+use std::sync::OnceLock;
+
+use vst::util::AtomicFloat;
+
+use crate::additive::HarmonicProfile;
+use crate::envelope::{segment_seconds, segment_to_param, Retrigger, LOOP_FOREVER, SEGMENT};
+use crate::euclidean::MAX_STEPS;
+use crate::filter::{FilterType, VOWELS};
+use crate::lfo::LfoShape;
+use crate::fm::OPERATORS;
+use crate::modulation::{Destination, MACROS, MACRO_TARGETS, MOD_SLOTS};
+use crate::noise::NoiseColor;
+use crate::note_sequencer::{velocity_to_param, NOTE_STEPS};
+use crate::organ::{self, FOOTAGES};
+use crate::oscillator::{PwmSource, SubShape, Waveform};
+use crate::voice::{Engine, NotePriority, StealPolicy, VelocityCurve};
+use crate::wavetable::Interpolation;
+use crate::{
+    bend_range_to_param, division_to_param, fm_operator_field, macro_field, max_voices_to_param,
+    mod_slot_field, note_step_field, on_off, ratio_to_param, LearnTarget, MacroField,
+    Patch, PARAMETERS, VOLUME,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    // Counts, choices and switches.
+    Plain,
+    // Read as a fraction, and shown a hundred times larger.
+    Percent,
+    Seconds,
+    Milliseconds,
+    Hz,
+    Decibels,
+    Semitones,
+    Cents,
+    Octaves,
+}
+
+impl Unit {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Unit::Plain => "",
+            Unit::Percent => "%",
+            Unit::Seconds => "s",
+            Unit::Milliseconds => "ms",
+            Unit::Hz => "Hz",
+            Unit::Decibels => "dB",
+            Unit::Semitones => "st",
+            Unit::Cents => "ct",
+            Unit::Octaves => "oct",
+        }
+    }
+}
+
+// Sections of the parameter list. Generic editors show a flat list, so each name leads
+// with its group to keep related parameters recognisable together.
+#[derive(Clone, Copy)]
+pub enum Group {
+    Global,
+    Osc,
+    Filter,
+    Env,
+    Lfo,
+    Mod,
+    Midi,
+    Voice,
+    Pattern,
+}
+
+impl Group {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Group::Global => "Global",
+            Group::Osc => "Osc",
+            Group::Filter => "Filter",
+            Group::Env => "Env",
+            Group::Lfo => "LFO",
+            Group::Mod => "Mod",
+            Group::Midi => "MIDI",
+            Group::Voice => "Voice",
+            Group::Pattern => "Pattern",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Mapping {
+    Linear,
+    // Evenly spaced in ratio, for times and frequencies.
+    Exponential,
+}
+
+// The plain values, in the parameter's unit, that a normalized value from 0 to 1 covers.
+#[derive(Clone, Copy)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+    pub mapping: Mapping,
+}
+
+impl Range {
+    pub const fn linear(min: f32, max: f32) -> Range {
+        Range {
+            min,
+            max,
+            mapping: Mapping::Linear,
+        }
+    }
+
+    pub const fn exponential(min: f32, max: f32) -> Range {
+        Range {
+            min,
+            max,
+            mapping: Mapping::Exponential,
+        }
+    }
+
+    pub fn plain(&self, value: f32) -> f32 {
+        match self.mapping {
+            Mapping::Linear => self.min + value * (self.max - self.min),
+            Mapping::Exponential => self.min * (self.max / self.min).powf(value),
+        }
+    }
+
+    // Values outside the range land on its nearer end.
+    pub fn normalized(&self, plain: f32) -> f32 {
+        let plain = plain.clamp(self.min.min(self.max), self.max.max(self.min));
+        match self.mapping {
+            Mapping::Linear => (plain - self.min) / (self.max - self.min),
+            Mapping::Exponential => (plain / self.min).ln() / (self.max / self.min).ln(),
+        }
+    }
+}
+
+// Where the parameter at the given index is kept.
+pub type Value = fn(&Patch, i32) -> &AtomicFloat;
+
+// Formats the value of the parameter at the given index, without its unit.
+pub type Text = fn(&Patch, i32) -> String;
+
+pub struct Descriptor {
+    pub group: Group,
+    // Within its group.
+    pub name: String,
+    pub unit: Unit,
+    // `None` for the sample and Save Preset, which take text rather than a number.
+    pub value: Option<Value>,
+    // Normalized, like everything the host sees.
+    pub default: f32,
+    // Only for values that map evenly onto a range; choices, counts and anything shaped
+    // more particularly read their own value.
+    pub range: Option<Range>,
+    pub automatable: bool,
+    pub text: Text,
+}
+
+impl Descriptor {
+    fn new(name: impl Into<String>, unit: Unit, text: Text) -> Descriptor {
+        Descriptor {
+            group: Group::Global,
+            name: name.into(),
+            unit,
+            value: None,
+            default: 0.0,
+            range: None,
+            automatable: true,
+            text,
+        }
+    }
+
+    fn value(self, value: Value, default: f32) -> Descriptor {
+        Descriptor {
+            value: Some(value),
+            default,
+            ..self
+        }
+    }
+
+    fn range(self, range: Range) -> Descriptor {
+        Descriptor {
+            range: Some(range),
+            ..self
+        }
+    }
+
+    fn fixed(self) -> Descriptor {
+        Descriptor {
+            automatable: false,
+            ..self
+        }
+    }
+
+    // What the host lists it as.
+    pub fn full_name(&self) -> String {
+        format!("{}: {}", self.group.name(), self.name)
+    }
+}
+
+// Entries in index order, each in whichever group was started last.
+struct Table {
+    entries: Vec<Descriptor>,
+    group: Group,
+}
+
+impl Table {
+    fn start(&mut self, group: Group) {
+        self.group = group;
+    }
+
+    fn push(&mut self, descriptor: Descriptor) {
+        self.entries.push(Descriptor {
+            group: self.group,
+            ..descriptor
+        });
+    }
+
+    fn extend(&mut self, descriptors: impl IntoIterator<Item = Descriptor>) {
+        for descriptor in descriptors {
+            self.push(descriptor);
+        }
+    }
+}
+
+// The indices the rest of the plugin reads parameters by, and where each run of
+// repeated entries starts. `build` checks every one against the entry it names, so a
+// parameter added or moved out of step with these stops the plugin from loading.
+pub const OSC2_FINE: i32 = 10;
+pub const SYNC_TUNE: i32 = 18;
+pub const PULSE_WIDTH: i32 = 19;
+pub const PWM_RATE: i32 = 21;
+// Six per operator: ratio, level, attack, decay, sustain and release.
+pub const FM_OPERATORS_START: i32 = 27;
+pub const HARMONIC_LEVELS_START: i32 = 53;
+pub const PLUCK_DECAY: i32 = 61;
+pub const UNISON_DETUNE: i32 = 65;
+pub const SAMPLE: i32 = 68;
+pub const GRAIN_SIZE: i32 = 71;
+pub const GRAIN_DENSITY: i32 = 72;
+pub const GRAIN_JITTER: i32 = 74;
+pub const DRUM_TUNE: i32 = 75;
+pub const DRUM_DECAY: i32 = 76;
+pub const DRAWBARS_START: i32 = 77;
+pub const VECTOR_WAVES_START: i32 = 87;
+pub const CUTOFF: i32 = 94;
+pub const FILTER_ENV_AMOUNT: i32 = 101;
+pub const KEY_TRACK: i32 = 102;
+pub const COMB_TUNE: i32 = 105;
+pub const MOD_ENV_AMOUNT: i32 = 121;
+pub const LFO_RATE: i32 = 123;
+pub const LFO_DEPTH: i32 = 124;
+pub const LFO_DELAY: i32 = 127;
+pub const LFO_FADE_IN: i32 = 128;
+pub const SAMPLE_HOLD_RATE: i32 = 129;
+pub const SAMPLE_HOLD_AMOUNT: i32 = 131;
+// Source, destination and amount per slot.
+pub const MOD_SLOTS_START: i32 = 133;
+// Each macro's knob, then a destination and amount per target.
+pub const MACROS_START: i32 = 157;
+pub const SEQUENCER_STEPS_START: i32 = 187;
+pub const CHAOS_RATE: i32 = 203;
+pub const BEND_RANGE: i32 = 205;
+pub const WHEEL_AMOUNT: i32 = 207;
+pub const MIDI_LEARN: i32 = 209;
+pub const GLIDE_TIME: i32 = 215;
+// Pitch, gate and velocity per step.
+pub const NOTE_STEPS_START: i32 = 226;
+pub const STRUM: i32 = 282;
+pub const SAVE_PRESET: i32 = 286;
+
+const NAMED: [(i32, &str); 38] = [
+    (OSC2_FINE, "Osc: Osc2 Fine"),
+    (SYNC_TUNE, "Osc: Sync Tune"),
+    (PULSE_WIDTH, "Osc: Pulse Width"),
+    (PWM_RATE, "Osc: PWM Rate"),
+    (FM_OPERATORS_START, "Osc: Op1 Ratio"),
+    (HARMONIC_LEVELS_START, "Osc: Harm 1"),
+    (PLUCK_DECAY, "Osc: Pluck Decay"),
+    (UNISON_DETUNE, "Osc: Unison Detune"),
+    (SAMPLE, "Osc: Sample"),
+    (GRAIN_SIZE, "Osc: Grain Size"),
+    (GRAIN_DENSITY, "Osc: Grain Density"),
+    (GRAIN_JITTER, "Osc: Grain Jitter"),
+    (DRUM_TUNE, "Osc: Drum Tune"),
+    (DRUM_DECAY, "Osc: Drum Decay"),
+    (DRAWBARS_START, "Osc: Drawbar 16'"),
+    (VECTOR_WAVES_START, "Osc: Vector Wave A"),
+    (CUTOFF, "Filter: Cutoff"),
+    (FILTER_ENV_AMOUNT, "Filter: Env Amount"),
+    (KEY_TRACK, "Filter: KeyTrack"),
+    (COMB_TUNE, "Filter: Comb Tune"),
+    (MOD_ENV_AMOUNT, "Env: Mod Env Amount"),
+    (LFO_RATE, "LFO: Rate"),
+    (LFO_DEPTH, "LFO: Depth"),
+    (LFO_DELAY, "LFO: Delay"),
+    (LFO_FADE_IN, "LFO: Fade In"),
+    (SAMPLE_HOLD_RATE, "LFO: S&H Rate"),
+    (SAMPLE_HOLD_AMOUNT, "LFO: S&H Amount"),
+    (MOD_SLOTS_START, "Mod: Slot1 Source"),
+    (MACROS_START, "Mod: Macro1"),
+    (SEQUENCER_STEPS_START, "LFO: Seq Step 1"),
+    (CHAOS_RATE, "LFO: Chaos Rate"),
+    (BEND_RANGE, "MIDI: Bend Range"),
+    (WHEEL_AMOUNT, "MIDI: Wheel Amount"),
+    (MIDI_LEARN, "MIDI: Learn"),
+    (GLIDE_TIME, "Voice: Glide Time"),
+    (NOTE_STEPS_START, "Pattern: Note1 Pitch"),
+    (STRUM, "Pattern: Strum"),
+    (SAVE_PRESET, "Global: Save Preset"),
+];
+
+// Every macro's knob, destinations and amounts together.
+const MACRO_PARAMETERS: i32 = (MACROS * (1 + 2 * MACRO_TARGETS)) as i32;
+
+pub fn table() -> &'static [Descriptor] {
+    static TABLE: OnceLock<Vec<Descriptor>> = OnceLock::new();
+    TABLE.get_or_init(build)
+}
+
+pub fn descriptor(index: i32) -> Option<&'static Descriptor> {
+    usize::try_from(index).ok().and_then(|index| table().get(index))
+}
+
+// The index of the parameter whose full name this is, ignoring case.
+pub fn index_of(full_name: &str) -> Option<i32> {
+    let position = table().iter().position(|d| d.full_name().eq_ignore_ascii_case(full_name));
+    position.map(|index| index as i32)
+}
+
+// The plain value of the parameter at the given index for a normalized `value`.
+pub fn plain(index: i32, value: f32) -> f32 {
+    match descriptor(index).and_then(|descriptor| descriptor.range) {
+        Some(range) => range.plain(value),
+        None => value,
+    }
+}
+
+// How finely `parse` walks a parameter's range. Fine enough to land on every choice of
+// the longest lists several times over.
+const PARSE_STEPS: usize = 4096;
+
+// Reads what was typed into the host's field as the normalized value for the parameter
+// at the given index: a choice by its name, or a number in the parameter's unit or a
+// related one, so "500ms" sets a time shown in seconds. A number for an entry with a
+// range comes straight back through it. Everything else only rises or only falls, so
+// this walks it reading `text` on a copy of the values and keeps the value that
+// reads as typed.
+pub fn parse(params: &Patch, index: i32, typed: &str) -> Option<f32> {
+    let descriptor = descriptor(index)?;
+    // The sample and Save Preset take text, and have no value to read.
+    descriptor.value?;
+    let typed = typed.trim();
+    // A suffix the values carry themselves, like the "x" on a multiplier.
+    let current = (descriptor.text)(params, index);
+    let own_suffix = split_number(&current).map(|(_, own)| own);
+    let target = split_number(typed).and_then(|(number, suffix)| {
+        match unit_scale(descriptor.unit, suffix) {
+            Some(scale) => Some(number * scale),
+            None if own_suffix.is_some_and(|own| own.eq_ignore_ascii_case(suffix.trim())) => {
+                Some(number)
+            }
+            None => None,
+        }
+    });
+    if let (Some(range), Some(target)) = (descriptor.range, target) {
+        let plain = if descriptor.unit == Unit::Percent { target / 100.0 } else { target };
+        return Some(range.normalized(plain));
+    }
+
+    // Only the values are copied, not the programs, but in full, so text that depends on
+    // another parameter, like the LFO rate on its sync, reads as it does now.
+    let scratch = Patch::default();
+    for other in 0..PARAMETERS {
+        scratch.set(other, params.get(other));
+    }
+    let read = |value: f32| {
+        scratch.set(index, value);
+        (descriptor.text)(&scratch, index)
+    };
+
+    // Neighbouring steps that read the same, as the first and last value and the text.
+    let mut runs: Vec<(f32, f32, String)> = Vec::new();
+    for step in 0..=PARSE_STEPS {
+        let value = step as f32 / PARSE_STEPS as f32;
+        let text = read(value);
+        match runs.last_mut() {
+            Some((_, last, previous)) if *previous == text => *last = value,
+            _ => runs.push((value, value, text)),
+        }
+    }
+    let middle = |run: &(f32, f32, String)| (run.0 + run.1) * 0.5;
+    if let Some(run) = runs.iter().find(|run| run.2.eq_ignore_ascii_case(typed)) {
+        return Some(middle(run));
+    }
+
+    let target = target?;
+    let numbers: Vec<Option<f32>> =
+        runs.iter().map(|run| split_number(&run.2).map(|(number, _)| number)).collect();
+    let distance = |number: f32| (number - target).abs();
+    let (closest, closest_number) = (numbers.iter().enumerate())
+        .filter_map(|(i, number)| Some((i, (*number)?)))
+        .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))?;
+    if same(closest_number, target) {
+        return Some(middle(&runs[closest]));
+    }
+
+    // The typed value falls between what two runs read, or finer than the text shows:
+    // take it from between the closest run and its neighbour on the far side.
+    let below = closest_number < target;
+    let neighbour = [closest.wrapping_sub(1), closest + 1].into_iter().find_map(|i| {
+        let number = numbers.get(i).copied().flatten()?;
+        ((number < target) != below).then_some((i, number))
+    });
+    let Some((neighbour, neighbour_number)) = neighbour else {
+        return Some(middle(&runs[closest]));
+    };
+    let (near, far) = (middle(&runs[closest]), middle(&runs[neighbour]));
+    let fraction = (target - closest_number) / (neighbour_number - closest_number);
+    Some(near + (far - near) * fraction)
+}
+
+// A leading number and whatever follows it: "-12dB" is -12 and "dB".
+fn split_number(text: &str) -> Option<(f32, &str)> {
+    let text = text.trim();
+    let end = text.find(|c: char| !(c.is_ascii_digit() || "+-.".contains(c))).unwrap_or(text.len());
+    let number = text[..end].parse().ok()?;
+    Some((number, &text[end..]))
+}
+
+// What a number typed with `suffix` is multiplied by to be in `unit`.
+fn unit_scale(unit: Unit, suffix: &str) -> Option<f32> {
+    let suffix = suffix.trim().to_ascii_lowercase();
+    match (unit, suffix.as_str()) {
+        (_, "") => Some(1.0),
+        (Unit::Seconds, "ms") => Some(0.001),
+        (Unit::Milliseconds, "s") => Some(1000.0),
+        (Unit::Hz, "khz") => Some(1000.0),
+        _ if suffix == unit.label().to_ascii_lowercase() => Some(1.0),
+        _ => None,
+    }
+}
+
+// Whether a value read back from its text is the one typed, allowing for the rounding
+// in a unit conversion.
+fn same(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-5 * b.abs().max(1.0)
+}
+
+// Shared by the free LFO, sample and hold and chaos.
+const RATE: Range = Range::exponential(0.05, 50.0);
+// Depths that go either way, from -1 to 1.
+const BIPOLAR: Range = Range::linear(-1.0, 1.0);
+// Levels and amounts kept as they read, and times stored directly in seconds.
+const UNIT: Range = Range::linear(0.0, 1.0);
+
+fn percent(params: &Patch, index: i32) -> String {
+    format!("{:.0}", params.get(index) * 100.0)
+}
+
+// Stored directly in seconds.
+fn seconds(params: &Patch, index: i32) -> String {
+    format!("{:.3}", params.get(index))
+}
+
+// Attack, decay and release times, which are exponential.
+fn segment(params: &Patch, index: i32) -> String {
+    format!("{:.3}", segment_seconds(params.get(index)))
+}
+
+fn build() -> Vec<Descriptor> {
+    use Descriptor as D;
+    use Group::*;
+    use Unit::*;
+
+    let mut table = Table {
+        entries: Vec::new(),
+        group: Global,
+    };
+    table.extend([
+        D::new("Volume", Decibels, |p, _| match p.volume_db() {
+            Some(db) => format!("{:+.1}", db),
+            None => "-inf".to_string(),
+        })
+        .value(|p, _| &p.volume, VOLUME.normalized(-6.0))
+        .range(VOLUME),
+    ]);
+    table.start(Env);
+    table.extend([
+        D::new("Attack", Seconds, segment)
+            .value(|p, _| &p.attack, segment_to_param(0.01))
+            .range(SEGMENT),
+        D::new("Decay", Seconds, segment)
+            .value(|p, _| &p.decay, segment_to_param(0.1))
+            .range(SEGMENT),
+        D::new("Sustain", Percent, percent).value(|p, _| &p.sustain, 0.5).range(UNIT),
+        D::new("Release", Seconds, segment)
+            .value(|p, _| &p.release, segment_to_param(0.1))
+            .range(SEGMENT),
+    ]);
+    table.start(Osc);
+    table.extend([
+        D::new("Waveform", Plain, |p, _| Waveform::from_param(p.waveform.get()).name().into())
+            .value(|p, _| &p.waveform, 0.0),
+        D::new("WT Position", Percent, percent).value(|p, _| &p.wt_position, 0.0).range(UNIT),
+        D::new("WT Interp", Plain, |p, _| {
+            Interpolation::from_param(p.wt_interpolation.get()).name().into()
+        })
+        .value(|p, _| &p.wt_interpolation, 1.0),
+        D::new("Osc2 Wave", Plain, |p, _| {
+            Waveform::from_param(p.osc2_waveform.get()).name().into()
+        })
+        .value(|p, _| &p.osc2_waveform, 0.0),
+        D::new("Osc2 Coarse", Semitones, |p, _| format!("{:+}", p.osc2_coarse_semitones()))
+            .value(|p, _| &p.osc2_coarse, 0.5),
+        D::new("Osc2 Fine", Cents, |p, _| format!("{:+.0}", p.osc2_fine_cents()))
+            .value(|p, _| &p.osc2_fine, 0.5)
+            .range(Range::linear(-100.0, 100.0)),
+        D::new("Osc Mix", Percent, percent).value(|p, _| &p.osc_mix, 0.0).range(UNIT),
+        D::new("Sub Shape", Plain, |p, _| SubShape::from_param(p.sub_shape.get()).name().into())
+            .value(|p, _| &p.sub_shape, 0.0),
+        D::new("Sub Octave", Octaves, |p, _| format!("-{}", p.sub_octaves()))
+            .value(|p, _| &p.sub_octave, 0.0),
+        D::new("Sub Level", Percent, percent).value(|p, _| &p.sub_level, 0.0).range(UNIT),
+        D::new("Noise Color", Plain, |p, _| {
+            NoiseColor::from_param(p.noise_color.get()).name().into()
+        })
+        .value(|p, _| &p.noise_color, 0.0),
+        D::new("Noise Level", Percent, percent).value(|p, _| &p.noise_level, 0.0).range(UNIT),
+        D::new("Sync", Plain, |p, _| on_off(p.sync_enabled())).value(|p, _| &p.sync, 0.0),
+        D::new("Sync Tune", Semitones, |p, _| format!("{:+.1}", p.sync_tune_semitones()))
+            .value(|p, _| &p.sync_tune, 0.0)
+            .range(Range::linear(0.0, 48.0)),
+        D::new("Pulse Width", Percent, |p, _| {
+            format!("{:.0}", p.pulse_width_fraction() * 100.0)
+        })
+        .value(|p, _| &p.pulse_width, 0.5)
+        .range(Range::linear(0.05, 0.95)),
+        D::new("PWM Depth", Percent, percent).value(|p, _| &p.pwm_depth, 0.0).range(UNIT),
+        D::new("PWM Rate", Hz, |p, _| format!("{:.2}", p.pwm_rate_hz()))
+            .value(|p, _| &p.pwm_rate, 0.2)
+            .range(Range::linear(0.1, 10.0)),
+        D::new("PWM Source", Plain, |p, _| {
+            PwmSource::from_param(p.pwm_source.get()).name().into()
+        })
+        .value(|p, _| &p.pwm_source, 0.0),
+        D::new("FM Amount", Percent, percent).value(|p, _| &p.fm_amount, 0.0).range(UNIT),
+        D::new("FM Ratio", Plain, |p, _| format!("{}", p.fm_ratio()))
+            .value(|p, _| &p.fm_ratio, ratio_to_param(1.0)),
+        D::new("Engine", Plain, |p, _| Engine::from_param(p.engine.get()).name().into())
+            .value(|p, _| &p.engine, 0.0),
+        D::new("FM Algorithm", Plain, |p, _| p.fm_algorithm().name.into())
+            .value(|p, _| &p.fm_algorithm, 0.0),
+    ]);
+    for index in FM_OPERATORS_START..FM_OPERATORS_START + 6 * OPERATORS as i32 {
+        let (op, field) = fm_operator_field(index);
+        let field_name = ["Ratio", "Level", "Attack", "Decay", "Sustain", "Release"][field];
+        let name = format!("Op{} {}", op + 1, field_name);
+        // The first two operators make a plain two-operator patch to start from.
+        let default = match field {
+            0 => ratio_to_param([1.0, 1.0, 2.0, 3.0][op]),
+            1 => [1.0, 0.5, 0.0, 0.0][op],
+            2 => segment_to_param(0.01),
+            3 => segment_to_param(0.3),
+            4 => 0.7,
+            _ => segment_to_param(0.2),
+        };
+        let descriptor = match field {
+            0 => D::new(name, Plain, |p, index| {
+                format!("{}", p.fm_operator_ratio(fm_operator_field(index).0))
+            }),
+            1 | 4 => D::new(name, Percent, percent).range(UNIT),
+            _ => D::new(name, Seconds, segment).range(SEGMENT),
+        };
+        table.push(descriptor.value(Patch::fm_operator_parameter, default));
+    }
+    table.extend([
+        D::new("Harmonics", Plain, |p, _| format!("{}", p.harmonic_count()))
+            .value(|p, _| &p.harmonics, 0.25),
+        D::new("Harm Profile", Plain, |p, _| {
+            HarmonicProfile::from_param(p.harmonic_profile.get()).name().into()
+        })
+        .value(|p, _| &p.harmonic_profile, 0.0),
+    ]);
+    // A saw's series until they're changed.
+    for harmonic in 1..=8 {
+        let descriptor = D::new(format!("Harm {}", harmonic), Percent, percent);
+        let value: Value = |p, index| &p.harmonic_levels[(index - HARMONIC_LEVELS_START) as usize];
+        table.push(descriptor.value(value, 1.0 / harmonic as f32).range(UNIT));
+    }
+    table.extend([
+        D::new("Pluck Decay", Seconds, |p, _| format!("{:.2}", p.pluck_decay_seconds()))
+            .value(|p, _| &p.pluck_decay, 0.5)
+            .range(Range::exponential(0.1, 10.0)),
+        D::new("Pluck Bright", Percent, percent).value(|p, _| &p.pluck_brightness, 0.5).range(UNIT),
+        D::new("PD Amount", Percent, percent).value(|p, _| &p.pd_amount, 0.0).range(UNIT),
+        D::new("Unison", Plain, |p, _| format!("{}", p.unison_voices()))
+            .value(|p, _| &p.unison, 0.0),
+        D::new("Unison Detune", Cents, |p, _| format!("{:.0}", p.unison_detune_cents()))
+            .value(|p, _| &p.unison_detune, 0.25)
+            .range(Range::linear(0.0, 100.0)),
+        D::new("Stereo Spread", Percent, percent).value(|p, _| &p.stereo_spread, 0.5).range(UNIT),
+        D::new("Ring Mod", Percent, percent).value(|p, _| &p.ring_mod, 0.0).range(UNIT),
+        D::new("Sample", Plain, |p, _| match p.sample.lock().unwrap().as_ref() {
+            Some(sample) => sample.name(),
+            None => "None".to_string(),
+        })
+        .fixed(),
+        D::new("Sample Start", Percent, percent).value(|p, _| &p.sample_start, 0.0).range(UNIT),
+        D::new("Sample Loop", Plain, |p, _| on_off(p.sample_loop.get() >= 0.5))
+            .value(|p, _| &p.sample_loop, 0.0),
+        D::new("Grain Size", Milliseconds, |p, _| {
+            format!("{:.0}", p.grain_size_seconds() * 1000.0)
+        })
+        .value(|p, _| &p.grain_size, 0.5)
+        .range(Range::exponential(10.0, 500.0)),
+        D::new("Grain Density", Hz, |p, _| format!("{:.1}", p.grain_density_hz()))
+            .value(|p, _| &p.grain_density, 0.5)
+            .range(Range::exponential(1.0, 100.0)),
+        D::new("Grain Position", Percent, percent).value(|p, _| &p.grain_position, 0.0).range(UNIT),
+        D::new("Grain Jitter", Semitones, |p, _| format!("{:.1}", p.grain_jitter_semitones()))
+            .value(|p, _| &p.grain_jitter, 0.0)
+            .range(Range::linear(0.0, 12.0)),
+        D::new("Drum Tune", Semitones, |p, _| format!("{:+.1}", p.drum_tune_semitones()))
+            .value(|p, _| &p.drum_tune, 0.5)
+            .range(Range::linear(-12.0, 12.0)),
+        D::new("Drum Decay", Plain, |p, _| format!("{:.2}x", p.drum_decay_scale()))
+            .value(|p, _| &p.drum_decay, 0.5)
+            .range(Range::exponential(0.25, 4.0)),
+    ]);
+    // The first three drawbars out.
+    for (i, footage) in FOOTAGES.iter().enumerate() {
+        let descriptor = D::new(format!("Drawbar {}", footage), Plain, |p, index| {
+            let value = p.drawbars[(index - DRAWBARS_START) as usize].get();
+            format!("{}", organ::drawbar_step(value))
+        });
+        let default = if i < 3 { 1.0 } else { 0.0 };
+        let value: Value = |p, index| &p.drawbars[(index - DRAWBARS_START) as usize];
+        table.push(descriptor.value(value, default));
+    }
+    table.push(D::new("Key Click", Percent, percent).value(|p, _| &p.key_click, 0.2).range(UNIT));
+    let vector_defaults = [Waveform::Sine, Waveform::Saw, Waveform::Square, Waveform::Triangle];
+    for (slot, waveform) in ["A", "B", "C", "D"].into_iter().zip(vector_defaults) {
+        let descriptor = D::new(format!("Vector Wave {}", slot), Plain, |p, index| {
+            let value = p.vector_waves[(index - VECTOR_WAVES_START) as usize].get();
+            Waveform::from_param(value).name().into()
+        });
+        table.push(descriptor.value(
+            |p, index| &p.vector_waves[(index - VECTOR_WAVES_START) as usize],
+            waveform.to_param(),
+        ));
+    }
+    table.extend([
+        D::new("Vector X", Percent, percent).value(|p, _| &p.vector_x, 0.0).range(UNIT),
+        D::new("Vector Y", Percent, percent).value(|p, _| &p.vector_y, 0.0).range(UNIT),
+        D::new("Drift", Percent, percent).value(|p, _| &p.drift, 0.0).range(UNIT),
+    ]);
+    table.start(Filter);
+    table.extend([
+        D::new("Cutoff", Hz, |p, _| format!("{:.0}", p.cutoff_hz()))
+            .value(|p, _| &p.cutoff, 1.0)
+            .range(Range::exponential(20.0, 20000.0)),
+        D::new("Resonance", Percent, percent).value(|p, _| &p.resonance, 0.0).range(UNIT),
+        D::new("Type", Plain, |p, _| {
+            FilterType::from_param(p.filter_type.get()).name().into()
+        })
+        .value(|p, _| &p.filter_type, 0.0),
+    ]);
+    table.start(Env);
+    table.extend([
+        D::new("Filt Attack", Seconds, segment)
+            .value(|p, _| &p.filter_attack, segment_to_param(0.01))
+            .range(SEGMENT),
+        D::new("Filt Decay", Seconds, segment)
+            .value(|p, _| &p.filter_decay, segment_to_param(0.3))
+            .range(SEGMENT),
+        D::new("Filt Sustain", Percent, percent).value(|p, _| &p.filter_sustain, 0.0).range(UNIT),
+        D::new("Filt Release", Seconds, segment)
+            .value(|p, _| &p.filter_release, segment_to_param(0.3))
+            .range(SEGMENT),
+    ]);
+    table.start(Filter);
+    table.extend([
+        D::new("Env Amount", Octaves, |p, _| format!("{:+.1}", p.filter_env_octaves()))
+            .value(|p, _| &p.filter_env_amount, 0.5)
+            .range(Range::linear(-8.0, 8.0)),
+        D::new("KeyTrack", Percent, |p, _| format!("{:.0}", p.key_track_amount() * 100.0))
+            .value(|p, _| &p.key_track, 0.0)
+            .range(Range::linear(0.0, 2.0)),
+        D::new("Drive", Percent, percent).value(|p, _| &p.drive, 0.0).range(UNIT),
+        D::new("Vowel", Plain, |p, _| {
+            let position = p.vowel.get() * (VOWELS.len() - 1) as f32;
+            VOWELS[(position.round() as usize).min(VOWELS.len() - 1)].into()
+        })
+        .value(|p, _| &p.vowel, 0.0),
+        D::new("Comb Tune", Semitones, |p, _| format!("{:+.1}", p.comb_tune_semitones()))
+            .value(|p, _| &p.comb_tune, 0.5)
+            .range(Range::linear(-24.0, 24.0)),
+    ]);
+    table.start(Env);
+    table.extend([
+        D::new("Attack Curve", Percent, percent).value(|p, _| &p.attack_curve, 0.0).range(UNIT),
+        D::new("Decay Curve", Percent, percent).value(|p, _| &p.decay_curve, 0.5).range(UNIT),
+        D::new("Release Curve", Percent, percent).value(|p, _| &p.release_curve, 0.5).range(UNIT),
+        D::new("Delay", Seconds, seconds).value(|p, _| &p.delay, 0.0).range(UNIT),
+        D::new("Hold", Seconds, seconds).value(|p, _| &p.hold, 0.0).range(UNIT),
+        D::new("Filt Delay", Seconds, seconds).value(|p, _| &p.filter_delay, 0.0).range(UNIT),
+        D::new("Filt Hold", Seconds, seconds).value(|p, _| &p.filter_hold, 0.0).range(UNIT),
+        D::new("Retrigger", Plain, |p, _| {
+            Retrigger::from_param(p.retrigger.get()).name().into()
+        })
+        .value(|p, _| &p.retrigger, 0.0),
+        D::new("Filt Loop", Plain, |p, _| match p.filter_env_loops() {
+            0 => "Off".to_string(),
+            LOOP_FOREVER => "Forever".to_string(),
+            loops => format!("{}x", loops),
+        })
+        .value(|p, _| &p.filter_loop, 0.0),
+        D::new("Vel > Env", Percent, percent)
+            .value(|p, _| &p.velocity_to_envelope, 0.5)
+            .range(UNIT),
+        D::new("Vel > Attack", Percent, percent)
+            .value(|p, _| &p.velocity_to_attack, 0.0)
+            .range(UNIT),
+        D::new("Mod Attack", Seconds, segment)
+            .value(|p, _| &p.mod_attack, segment_to_param(0.01))
+            .range(SEGMENT),
+        D::new("Mod Decay", Seconds, segment)
+            .value(|p, _| &p.mod_decay, segment_to_param(0.3))
+            .range(SEGMENT),
+        D::new("Mod Sustain", Percent, percent).value(|p, _| &p.mod_sustain, 0.0).range(UNIT),
+        D::new("Mod Release", Seconds, segment)
+            .value(|p, _| &p.mod_release, segment_to_param(0.3))
+            .range(SEGMENT),
+        D::new("Mod Env Amount", Percent, |p, _| format!("{:+.0}", p.mod_env_depth() * 100.0))
+            .value(|p, _| &p.mod_env_amount, 0.5)
+            .range(BIPOLAR),
+        D::new("Mod Env Dest", Plain, |p, _| p.mod_env_destination().name().into())
+            .value(|p, _| &p.mod_env_destination, 0.0),
+    ]);
+    table.start(Lfo);
+    table.extend([
+        // Synced rates read as a note division, which `get_parameter_label` leaves bare.
+        D::new("Rate", Hz, |p, _| match p.lfo_synced() {
+            true => p.lfo_division().0.into(),
+            false => format!("{:.2}", p.lfo_free_rate_hz()),
+        })
+        .value(|p, _| &p.lfo_rate, 0.5)
+        .range(RATE),
+        D::new("Depth", Semitones, |p, _| format!("{:.2}", p.lfo_depth_semitones(0.0)))
+            .value(|p, _| &p.lfo_depth, 0.0)
+            .range(Range::linear(0.0, 12.0)),
+        D::new("Shape", Plain, |p, _| LfoShape::from_param(p.lfo_shape.get()).name().into())
+            .value(|p, _| &p.lfo_shape, 0.0),
+        D::new("Sync", Plain, |p, _| on_off(p.lfo_synced())).value(|p, _| &p.lfo_sync, 0.0),
+        D::new("Delay", Seconds, |p, _| format!("{:.2}", p.lfo_delay_seconds()))
+            .value(|p, _| &p.lfo_delay, 0.0)
+            .range(Range::linear(0.0, 4.0)),
+        D::new("Fade In", Seconds, |p, _| format!("{:.2}", p.lfo_fade_seconds()))
+            .value(|p, _| &p.lfo_fade, 0.0)
+            .range(Range::linear(0.0, 4.0)),
+        D::new("S&H Rate", Hz, |p, _| format!("{:.2}", p.sample_hold_rate_hz()))
+            .value(|p, _| &p.sample_hold_rate, 0.5)
+            .range(RATE),
+        D::new("S&H Smooth", Percent, percent).value(|p, _| &p.sample_hold_smooth, 0.0).range(UNIT),
+        D::new("S&H Amount", Percent, |p, _| {
+            format!("{:+.0}", p.sample_hold_depth() * 100.0)
+        })
+        .value(|p, _| &p.sample_hold_amount, 0.5)
+        .range(BIPOLAR),
+        D::new("S&H Dest", Plain, |p, _| p.sample_hold_destination().name().into())
+            .value(|p, _| &p.sample_hold_destination, 0.0),
+    ]);
+    table.start(Mod);
+    for index in MOD_SLOTS_START..MOD_SLOTS_START + 3 * MOD_SLOTS as i32 {
+        let (slot, field) = mod_slot_field(index);
+        let name = format!("Slot{} {}", slot + 1, ["Source", "Dest", "Amount"][field]);
+        let descriptor = match field {
+            0 => D::new(name, Plain, |p, index| {
+                p.mod_slots[mod_slot_field(index).0].source().name().into()
+            }),
+            1 => D::new(name, Plain, |p, index| {
+                p.mod_slots[mod_slot_field(index).0].destination().name().into()
+            }),
+            _ => D::new(name, Percent, |p, index| {
+                format!("{:+.0}", p.mod_slots[mod_slot_field(index).0].depth() * 100.0)
+            })
+            .range(BIPOLAR),
+        };
+        // Amounts start in the middle, which is none.
+        let default = if field == 2 { 0.5 } else { 0.0 };
+        table.push(descriptor.value(Patch::mod_slot_parameter, default));
+    }
+    for index in MACROS_START..MACROS_START + MACRO_PARAMETERS {
+        let (knob, field) = macro_field(index);
+        let descriptor = match field {
+            MacroField::Value => D::new(format!("Macro{}", knob + 1), Percent, percent).range(UNIT),
+            MacroField::Destination(target) => {
+                let name = format!("Macro{} Dest{}", knob + 1, target + 1);
+                D::new(name, Plain, |p, index| match macro_field(index) {
+                    (knob, MacroField::Destination(target)) => {
+                        p.macros[knob].destination(target).name().into()
+                    }
+                    _ => String::new(),
+                })
+            }
+            MacroField::Amount(target) => {
+                let name = format!("Macro{} Amt{}", knob + 1, target + 1);
+                D::new(name, Percent, |p, index| match macro_field(index) {
+                    (knob, MacroField::Amount(target)) => {
+                        format!("{:+.0}", p.macros[knob].depth(target) * 100.0)
+                    }
+                    _ => String::new(),
+                })
+                .range(BIPOLAR)
+            }
+        };
+        let default = if let MacroField::Amount(_) = field { 0.5 } else { 0.0 };
+        table.push(descriptor.value(Patch::macro_parameter, default));
+    }
+    table.start(Lfo);
+    table.extend([
+        D::new("Seq Rate", Plain, |p, _| p.sequencer_division().0.into())
+            .value(|p, _| &p.sequencer_rate, division_to_param("1/16")),
+        D::new("Seq Glide", Percent, percent).value(|p, _| &p.sequencer_glide, 0.0).range(UNIT),
+    ]);
+    for step in 1..=16 {
+        let descriptor = D::new(format!("Seq Step {}", step), Percent, |p, index| {
+            let step = (index - SEQUENCER_STEPS_START) as usize;
+            format!("{:+.0}", p.sequencer_values()[step] * 100.0)
+        })
+        .range(BIPOLAR);
+        let value: Value = |p, index| &p.sequencer_steps[(index - SEQUENCER_STEPS_START) as usize];
+        table.push(descriptor.value(value, 0.5));
+    }
+    table.extend([
+        D::new("Chaos Rate", Hz, |p, _| format!("{:.2}", p.chaos_rate_hz()))
+            .value(|p, _| &p.chaos_rate, 0.3)
+            .range(RATE),
+        D::new("Chaos Amount", Percent, percent).value(|p, _| &p.chaos_amount, 1.0).range(UNIT),
+    ]);
+    table.start(Midi);
+    table.extend([
+        D::new("Bend Range", Semitones, |p, _| format!("{}", p.bend_range_semitones()))
+            .value(|p, _| &p.bend_range, bend_range_to_param(2)),
+        D::new("Wheel Dest", Plain, |p, _| p.mod_wheel_destination().name().into())
+            .value(|p, _| &p.mod_wheel_destination, Destination::LfoDepth.to_param()),
+        D::new("Wheel Amount", Percent, |p, _| format!("{:+.0}", p.mod_wheel_depth() * 100.0))
+            .value(|p, _| &p.mod_wheel_amount, 0.55)
+            .range(BIPOLAR),
+        D::new("MPE", Plain, |p, _| on_off(p.mpe_enabled())).value(|p, _| &p.mpe, 0.0),
+        D::new("Learn", Plain, |p, _| match p.learn_target() {
+            LearnTarget::Off => "Off".to_string(),
+            LearnTarget::Forget => "Forget".to_string(),
+            LearnTarget::Parameter(index) => {
+                descriptor(index).map_or(String::new(), |d| d.full_name())
+            }
+        })
+        .value(|p, _| &p.midi_learn, 0.0),
+        D::new("Channel", Plain, |p, _| match p.input_channel() {
+            Some(channel) => format!("{}", channel + 1),
+            None => "Omni".to_string(),
+        })
+        .value(|p, _| &p.midi_channel, 0.0),
+        D::new("Vel Curve", Plain, |p, _| {
+            VelocityCurve::from_param(p.velocity_curve.get()).name().into()
+        })
+        .value(|p, _| &p.velocity_curve, VelocityCurve::Linear.to_param()),
+    ]);
+    table.start(Voice);
+    table.extend([
+        D::new("Mode", Plain, |p, _| p.voice_mode().name().into())
+            .value(|p, _| &p.voice_mode, 0.0),
+        D::new("Note Priority", Plain, |p, _| {
+            NotePriority::from_param(p.note_priority.get()).name().into()
+        })
+        .value(|p, _| &p.note_priority, 0.0),
+        D::new("Glide", Plain, |p, _| on_off(p.glide_enabled())).value(|p, _| &p.glide, 0.0),
+        D::new("Glide Time", Seconds, |p, _| format!("{:.2}", p.glide_seconds()))
+            .value(|p, _| &p.glide_time, 0.05)
+            .range(Range::linear(0.0, 2.0)),
+        D::new("Steal", Plain, |p, _| {
+            StealPolicy::from_param(p.steal_policy.get()).name().into()
+        })
+        .value(|p, _| &p.steal_policy, 0.0),
+        D::new("Max Voices", Plain, |p, _| format!("{}", p.max_voices()))
+            .value(|p, _| &p.max_voices, max_voices_to_param(16)),
+    ]);
+    table.start(Midi);
+    table.extend([
+        D::new("Note Output", Plain, |p, _| p.note_output().name().into())
+            .value(|p, _| &p.note_output, 0.0),
+    ]);
+    table.start(Pattern);
+    table.extend([
+        D::new("Arp", Plain, |p, _| on_off(p.arp_enabled())).value(|p, _| &p.arp, 0.0),
+        D::new("Arp Mode", Plain, |p, _| p.arp_mode().name().into()).value(|p, _| &p.arp_mode, 0.0),
+        D::new("Arp Octaves", Plain, |p, _| format!("{}", p.arp_octaves()))
+            .value(|p, _| &p.arp_octaves, 0.0),
+        D::new("Arp Rate", Plain, |p, _| p.arp_division().0.into())
+            .value(|p, _| &p.arp_rate, division_to_param("1/16")),
+        D::new("Arp Gate", Percent, |p, _| format!("{:.0}", p.arp_gate() * 100.0))
+            .value(|p, _| &p.arp_gate, 0.5),
+        D::new("Note Seq", Plain, |p, _| on_off(p.note_seq_enabled()))
+            .value(|p, _| &p.note_seq, 0.0),
+        D::new("Note Seq Rate", Plain, |p, _| p.note_seq_division().0.into())
+            .value(|p, _| &p.note_seq_rate, division_to_param("1/16")),
+    ]);
+    for index in NOTE_STEPS_START..NOTE_STEPS_START + 3 * NOTE_STEPS as i32 {
+        let (step, field) = note_step_field(index);
+        let name = format!("Note{} {}", step + 1, ["Pitch", "Gate", "Vel"][field]);
+        let descriptor = match field {
+            0 => D::new(name, Semitones, |p, index| {
+                format!("{:+}", p.note_steps[note_step_field(index).0].step().pitch)
+            }),
+            1 => D::new(name, Percent, |p, index| {
+                let gate = p.note_steps[note_step_field(index).0].step().gate;
+                if gate == 0.0 {
+                    "Rest".to_string()
+                } else {
+                    format!("{:.0}", gate * 100.0)
+                }
+            }),
+            _ => D::new(name, Plain, |p, index| {
+                format!("{}", p.note_steps[note_step_field(index).0].step().velocity)
+            }),
+        };
+        // No transposition and half-length gates, at velocity 100.
+        let default = if field == 2 { velocity_to_param(100) } else { 0.5 };
+        table.push(descriptor.value(Patch::note_step_parameter, default));
+    }
+    table.extend([
+        D::new("Euclid", Plain, |p, _| on_off(p.euclid_enabled())).value(|p, _| &p.euclid, 0.0),
+        D::new("Euclid Rate", Plain, |p, _| p.euclid_division().0.into())
+            .value(|p, _| &p.euclid_rate, division_to_param("1/16")),
+        // Three hits in eight steps.
+        D::new("Euclid Steps", Plain, |p, _| format!("{}", p.euclid_rhythm().steps))
+            .value(|p, _| &p.euclid_steps, 7.0 / (MAX_STEPS - 1) as f32),
+        D::new("Euclid Pulses", Plain, |p, _| format!("{}", p.euclid_rhythm().pulses))
+            .value(|p, _| &p.euclid_pulses, 3.0 / MAX_STEPS as f32),
+        D::new("Euclid Rotate", Plain, |p, _| format!("{}", p.euclid_rhythm().rotation))
+            .value(|p, _| &p.euclid_rotation, 0.0),
+        D::new("Euclid Gate", Percent, |p, _| format!("{:.0}", p.euclid_gate() * 100.0))
+            .value(|p, _| &p.euclid_gate, 0.25),
+        D::new("Chord", Plain, |p, _| p.chord_mode().name().into())
+            .value(|p, _| &p.chord_mode, 0.0),
+        D::new("Chord Learn", Plain, |p, _| on_off(p.chord_learning()))
+            .value(|p, _| &p.chord_learn, 0.0),
+        D::new("Strum", Milliseconds, |p, _| format!("{:.0}", p.strum_seconds() * 1000.0))
+            .value(|p, _| &p.strum, 0.0)
+            .range(Range::linear(0.0, 50.0)),
+        D::new("Humanize", Percent, percent).value(|p, _| &p.humanize, 0.0).range(UNIT),
+        D::new("Transport Reset", Plain, |p, _| on_off(p.transport_reset_enabled()))
+            .value(|p, _| &p.transport_reset, 0.0),
+    ]);
+    table.start(Global);
+    table.extend([
+        D::new("Quality", Plain, |p, _| p.quality().name().into()).value(|p, _| &p.quality, 0.0),
+        D::new("Save Preset", Plain, |_, _| "Type a name to save".into()).fixed(),
+    ]);
+    // Debug builds only: a shipped plugin must never panic into the host.
+    debug_assert_eq!(table.entries.len(), PARAMETERS as usize);
+    for (index, full_name) in NAMED {
+        debug_assert_eq!(table.entries[index as usize].full_name(), full_name);
+    }
+    table.entries
+}
@@ -0,0 +1,72 @@
+// Slow per-voice wander of pitch and level, like an analog poly whose oscillators and
+// VCAs are never quite matched. Each is a leaky random walk stepped at a control rate
+// and smoothed in between.
+
+use crate::random::Random;
+
+// Calls between random-walk steps.
+const UPDATE_INTERVAL: u32 = 4;
+// Time the walk takes to lose track of where it started, in seconds.
+const WANDER_SECONDS: f32 = 2.0;
+const SMOOTHING_SECONDS: f32 = 0.05;
+const MAX_CENTS: f32 = 12.0;
+const MAX_LEVEL_DB: f32 = 1.5;
+
+#[derive(Clone, Copy, Default)]
+struct Walk {
+    target: f32,
+    value: f32,
+}
+
+impl Walk {
+    fn scatter(&mut self, rng: &mut Random) {
+        self.target = rng.bipolar() * 0.5;
+        self.value = self.target;
+    }
+
+    // Ornstein-Uhlenbeck step that settles to a spread of about ±0.5.
+    fn step(&mut self, leak: f32, rng: &mut Random) {
+        let size = 0.5 * (3.0 * (1.0 - leak * leak)).sqrt();
+        let white = rng.bipolar();
+        self.target = (self.target * leak + white * size).clamp(-1.0, 1.0);
+    }
+
+    fn smooth(&mut self, coeff: f32) -> f32 {
+        self.value += (self.target - self.value) * coeff;
+        self.value
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Drift {
+    countdown: u32,
+    pitch: Walk,
+    level: Walk,
+    rng: Random,
+}
+
+impl Drift {
+    // Voices start from different offsets so a fresh chord is already slightly detuned.
+    pub fn reset(&mut self) {
+        self.countdown = 0;
+        self.pitch.scatter(&mut self.rng);
+        self.level.scatter(&mut self.rng);
+    }
+
+    // Returns the frequency ratio and gain to apply until the next call, `seconds` from
+    // now; `amount` runs from none to the full ±12 cents and ±1.5 dB.
+    pub fn next(&mut self, amount: f32, seconds: f32) -> (f32, f32) {
+        if self.countdown == 0 {
+            self.countdown = UPDATE_INTERVAL;
+            let leak = (-(UPDATE_INTERVAL as f32) * seconds / WANDER_SECONDS).exp();
+            self.pitch.step(leak, &mut self.rng);
+            self.level.step(leak, &mut self.rng);
+        }
+        self.countdown -= 1;
+
+        let coeff = 1.0 - (-seconds / SMOOTHING_SECONDS).exp();
+        let cents = self.pitch.smooth(coeff) * amount * MAX_CENTS;
+        let db = self.level.smooth(coeff) * amount * MAX_LEVEL_DB;
+        ((cents / 1200.0).exp2(), 10.0f32.powf(db / 20.0))
+    }
+}
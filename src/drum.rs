@@ -0,0 +1,124 @@
+use std::f32::consts::PI;
+
+use crate::sine;
+use crate::random::Random;
+
+#[derive(Clone, Copy, PartialEq)]
+enum DrumKind {
+    Kick,
+    Snare,
+    ClosedHat,
+    OpenHat,
+    Tom(f32),
+}
+
+impl DrumKind {
+    // Follows the General MIDI percussion map.
+    fn for_note(note: u8) -> Option<DrumKind> {
+        match note {
+            35 | 36 => Some(DrumKind::Kick),
+            38 | 40 => Some(DrumKind::Snare),
+            42 | 44 => Some(DrumKind::ClosedHat),
+            46 => Some(DrumKind::OpenHat),
+            41 => Some(DrumKind::Tom(80.0)),
+            43 => Some(DrumKind::Tom(100.0)),
+            45 => Some(DrumKind::Tom(125.0)),
+            47 => Some(DrumKind::Tom(150.0)),
+            48 => Some(DrumKind::Tom(180.0)),
+            50 => Some(DrumKind::Tom(220.0)),
+            _ => None,
+        }
+    }
+
+    // Longest amplitude time constant of the model, in seconds, before decay scaling.
+    fn length(&self) -> f32 {
+        match self {
+            DrumKind::Kick => 0.3,
+            DrumKind::Snare => 0.15,
+            DrumKind::ClosedHat => 0.03,
+            DrumKind::OpenHat => 0.25,
+            DrumKind::Tom(_) => 0.25,
+        }
+    }
+}
+
+// Time constants a drum rings for: e^-7 is about -60 dB.
+const RING_CONSTANTS: f32 = 7.0;
+
+// Seconds the longest drum rings for at `decay`.
+pub fn longest_ring(decay: f32) -> f32 {
+    DrumKind::Kick.length() * decay * RING_CONSTANTS
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DrumVoice {
+    kind: Option<DrumKind>,
+    time: f32,
+    phase: f32,
+    noise_input: f32,
+    noise_output: f32,
+    rng: Random,
+}
+
+impl DrumVoice {
+    pub fn start(&mut self, note: u8) {
+        *self = DrumVoice {
+            kind: DrumKind::for_note(note),
+            rng: self.rng,
+            ..DrumVoice::default()
+        };
+    }
+
+    // Drums are one-shots: they end once the longest segment has decayed by ~60 dB,
+    // regardless of note-off.
+    pub fn finished(&self, decay: f32) -> bool {
+        match self.kind {
+            Some(kind) => self.time >= kind.length() * decay * RING_CONSTANTS,
+            None => true,
+        }
+    }
+
+    // `tune` scales every pitch and `decay` every amplitude time constant.
+    pub fn next(&mut self, per_sample: f32, tune: f32, decay: f32) -> f32 {
+        let t = self.time;
+        let fall = |seconds: f32| (-t / (seconds * decay)).exp();
+
+        let out = match self.kind {
+            Some(DrumKind::Kick) => {
+                let freq = (50.0 + 100.0 * (-t / 0.04).exp()) * tune;
+                let click = self.noise(per_sample, 2000.0) * (-t / 0.003).exp() * 0.3;
+                self.tone(freq, per_sample) * fall(0.3) + click
+            }
+            Some(DrumKind::Snare) => {
+                let body = self.tone(185.0 * tune, per_sample) * fall(0.08) * 0.5;
+                body + self.noise(per_sample, 1500.0) * fall(0.15) * 0.7
+            }
+            Some(DrumKind::ClosedHat) => self.noise(per_sample, 7000.0) * fall(0.03),
+            Some(DrumKind::OpenHat) => self.noise(per_sample, 7000.0) * fall(0.25),
+            Some(DrumKind::Tom(base)) => {
+                let freq = base * (1.0 + 0.5 * (-t / 0.05).exp()) * tune;
+                self.tone(freq, per_sample) * fall(0.25)
+            }
+            None => 0.0,
+        };
+
+        self.time += per_sample;
+        out
+    }
+
+    fn tone(&mut self, freq: f32, per_sample: f32) -> f32 {
+        let out = sine::sine(self.phase);
+        self.phase = (self.phase + freq * per_sample).fract();
+        out
+    }
+
+    // White noise through a one-pole high-pass at `cutoff` Hz.
+    fn noise(&mut self, per_sample: f32, cutoff: f32) -> f32 {
+        let white = self.rng.bipolar();
+        let rc = 1.0 / (2.0 * PI * cutoff);
+        let a = rc / (rc + per_sample);
+        self.noise_output = a * (self.noise_output + white - self.noise_input);
+        self.noise_input = white;
+        self.noise_output
+    }
+}
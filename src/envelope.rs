@@ -0,0 +1,195 @@
+use crate::descriptor::Range;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Stage {
+    #[default]
+    Idle,
+    Delay,
+    Attack,
+    Hold,
+    Decay,
+    Sustain,
+    Release,
+}
+
+impl Stage {
+    fn after(self) -> Stage {
+        match self {
+            Stage::Delay => Stage::Attack,
+            Stage::Attack => Stage::Hold,
+            Stage::Hold => Stage::Decay,
+            Stage::Decay | Stage::Sustain => Stage::Sustain,
+            Stage::Release | Stage::Idle => Stage::Idle,
+        }
+    }
+}
+
+// What a new note does to its voice's envelopes while other keys are held.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Retrigger {
+    // Restart every note from silence.
+    Zero,
+    // Attack from wherever the previous note's envelopes had got to.
+    Current,
+    // Pick up the previous note's envelopes as they are, with no new attack.
+    Legato,
+}
+
+impl Retrigger {
+    const ALL: [Retrigger; 3] = [Retrigger::Zero, Retrigger::Current, Retrigger::Legato];
+
+    pub fn from_param(value: f32) -> Retrigger {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Retrigger::Zero => "From Zero",
+            Retrigger::Current => "From Current",
+            Retrigger::Legato => "Legato",
+        }
+    }
+}
+
+// How far each segment bends, from 0 (a straight line) to 1 (a steep exponential).
+#[derive(Clone, Copy, Default)]
+pub struct EnvelopeCurves {
+    pub attack: f32,
+    pub decay: f32,
+    pub release: f32,
+}
+
+// Pass as `EnvelopeSettings::loops` to loop for as long as the key is held.
+pub const LOOP_FOREVER: u32 = u32::MAX;
+
+// Attack, decay and release knobs are exponential, so the short times that most sounds
+// use get most of the travel: the middle of the knob is about 140 ms.
+pub const SEGMENT: Range = Range::exponential(0.001, 20.0);
+
+pub fn segment_seconds(value: f32) -> f32 {
+    SEGMENT.plain(value)
+}
+
+pub fn segment_to_param(seconds: f32) -> f32 {
+    SEGMENT.normalized(seconds)
+}
+
+// Segment times in seconds; sustain is a level from 0 to 1.
+#[derive(Clone, Copy, Default)]
+pub struct EnvelopeSettings {
+    pub delay: f32,
+    pub attack: f32,
+    pub hold: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub curves: EnvelopeCurves,
+    // Times to go back from the end of the decay to the start of the attack before
+    // settling on the sustain level.
+    pub loops: u32,
+}
+
+// DAHDSR stepped one sample at a time. Triggering and releasing both carry on from the
+// current level, so a retriggered or released note never jumps.
+#[derive(Clone, Copy, Default)]
+pub struct Envelope {
+    stage: Stage,
+    level: f32,
+    // Level the current segment started from.
+    start: f32,
+    // How far through the current segment we are, from 0 to 1.
+    progress: f32,
+    loops_done: u32,
+}
+
+impl Envelope {
+    pub fn trigger(&mut self) {
+        self.loops_done = 0;
+        self.enter(Stage::Delay);
+    }
+
+    // Attack from `level` rather than from wherever this envelope is.
+    pub fn trigger_from(&mut self, level: f32) {
+        self.level = level;
+        self.trigger();
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn release(&mut self) {
+        if self.stage != Stage::Idle {
+            self.enter(Stage::Release);
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.stage == Stage::Idle
+    }
+
+    pub fn next(&mut self, settings: &EnvelopeSettings, per_sample: f32) -> f32 {
+        let sustain = settings.sustain.clamp(0.0, 1.0);
+        let curves = &settings.curves;
+
+        // Delay and hold are optional; skip them outright rather than spend a sample in
+        // each when they're zero.
+        while (self.stage == Stage::Delay && settings.delay <= 0.0)
+            || (self.stage == Stage::Hold && settings.hold <= 0.0)
+        {
+            self.enter(self.stage.after());
+        }
+
+        // Every timed segment moves from the level it started at towards a target.
+        let (seconds, target, bend) = match self.stage {
+            Stage::Idle => {
+                self.level = 0.0;
+                return self.level;
+            }
+            Stage::Sustain => {
+                self.level = sustain;
+                return self.level;
+            }
+            Stage::Delay => (settings.delay, self.start, 0.0),
+            Stage::Attack => (settings.attack, 1.0, curves.attack),
+            Stage::Hold => (settings.hold, 1.0, 0.0),
+            Stage::Decay => (settings.decay, sustain, curves.decay),
+            Stage::Release => (settings.release, 0.0, curves.release),
+        };
+
+        // A zero-length segment is over in one sample.
+        self.progress += per_sample / seconds.max(per_sample);
+        self.level = self.start + (target - self.start) * curve(self.progress, bend);
+        if self.progress >= 1.0 {
+            if self.stage == Stage::Decay && self.loops_done < settings.loops {
+                if settings.loops != LOOP_FOREVER {
+                    self.loops_done += 1;
+                }
+                self.enter(Stage::Attack);
+            } else {
+                self.enter(self.stage.after());
+            }
+        }
+        self.level
+    }
+
+    fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.start = self.level;
+        self.progress = 0.0;
+    }
+}
+
+// Maps progress through a segment to how far the level has moved towards its target.
+// Bent segments follow an RC charge curve: quick at first, settling gently at the end,
+// as on an analog envelope.
+fn curve(progress: f32, amount: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    let k = amount.clamp(0.0, 1.0) * 8.0;
+    if k < 1e-3 {
+        progress
+    } else {
+        (1.0 - (-k * progress).exp()) / (1.0 - (-k).exp())
+    }
+}
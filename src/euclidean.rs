@@ -0,0 +1,134 @@
+use crate::arpeggiator::ArpNote;
+
+pub const MAX_STEPS: usize = 16;
+const MAX_KEYS: usize = 32;
+
+// `pulses` hits spread as evenly as they go over `steps`, turned `rotation` steps
+// earlier: 3 in 8 is the tresillo, x..x..x.
+#[derive(Clone, Copy)]
+pub struct Rhythm {
+    pub steps: usize,
+    pub pulses: usize,
+    pub rotation: usize,
+}
+
+impl Rhythm {
+    fn hit(&self, step: usize) -> bool {
+        (step + self.rotation) * self.pulses % self.steps < self.pulses
+    }
+}
+
+// A set of keys, oldest first.
+#[derive(Clone, Copy)]
+pub struct Chord {
+    notes: [ArpNote; MAX_KEYS],
+    len: usize,
+}
+
+impl Default for Chord {
+    fn default() -> Chord {
+        Chord {
+            notes: [ArpNote::default(); MAX_KEYS],
+            len: 0,
+        }
+    }
+}
+
+impl Chord {
+    pub fn notes(&self) -> &[ArpNote] {
+        &self.notes[..self.len]
+    }
+
+    fn push(&mut self, note: ArpNote) {
+        self.remove(note.channel, note.note);
+        if self.len < MAX_KEYS {
+            self.notes[self.len] = note;
+            self.len += 1;
+        }
+    }
+
+    fn remove(&mut self, channel: u8, note: u8) {
+        let held = self.notes();
+        if let Some(index) = held.iter().position(|h| h.channel == channel && h.note == note) {
+            self.notes.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+}
+
+// The keys that end on one sample, then the ones that start.
+#[derive(Default)]
+pub struct Hit {
+    pub off: Option<Chord>,
+    pub on: Option<Chord>,
+}
+
+// Plays every held key together on each hit of a Euclidean rhythm. Keys pressed between
+// hits wait for the next one.
+pub struct Euclidean {
+    held: Chord,
+    playing: Option<Chord>,
+    step: usize,
+    // Progress through the current step; at 1 the next one is due.
+    phase: f32,
+}
+
+impl Default for Euclidean {
+    fn default() -> Euclidean {
+        Euclidean {
+            held: Chord::default(),
+            playing: None,
+            step: 0,
+            phase: 1.0,
+        }
+    }
+}
+
+impl Euclidean {
+    pub fn press(&mut self, channel: u8, note: u8, velocity: u8) {
+        // The first key starts the rhythm from its first step, straight away.
+        if self.held.len == 0 {
+            self.step = 0;
+            self.phase = 1.0;
+        }
+        self.held.push(ArpNote { channel, note, velocity });
+    }
+
+    pub fn release(&mut self, channel: u8, note: u8) {
+        self.held.remove(channel, note);
+    }
+
+    // Goes back to the first step, which plays at once if keys are down.
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.phase = 1.0;
+    }
+
+    // Forgets every key and returns the notes left sounding, which need note-offs.
+    pub fn stop(&mut self) -> Option<Chord> {
+        self.held.len = 0;
+        self.playing.take()
+    }
+
+    // Advances by one sample. `gate` is the fraction of each step a hit is held for.
+    pub fn next(&mut self, rhythm: Rhythm, rate_hz: f32, gate: f32, per_sample: f32) -> Hit {
+        let mut hit = Hit::default();
+        if self.held.len == 0 {
+            hit.off = self.playing.take();
+            return hit;
+        }
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            hit.off = self.playing.take();
+            if rhythm.hit(self.step) {
+                self.playing = Some(self.held);
+            }
+            hit.on = self.playing;
+            self.step = (self.step + 1) % rhythm.steps;
+        } else if self.phase >= gate {
+            hit.off = self.playing.take();
+        }
+        self.phase += rate_hz * per_sample;
+        hit
+    }
+}
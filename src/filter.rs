@@ -0,0 +1,383 @@
+use std::f32::consts::PI;
+
+use crate::ladder::Ladder;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterType {
+    #[default]
+    Ladder,
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    Formant,
+    CombPositive,
+    CombNegative,
+}
+
+impl FilterType {
+    const ALL: [FilterType; 8] = [
+        FilterType::Ladder,
+        FilterType::LowPass,
+        FilterType::HighPass,
+        FilterType::BandPass,
+        FilterType::Notch,
+        FilterType::Formant,
+        FilterType::CombPositive,
+        FilterType::CombNegative,
+    ];
+
+    pub fn from_param(value: f32) -> FilterType {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterType::Ladder => "Ladder LP",
+            FilterType::LowPass => "SVF LP",
+            FilterType::HighPass => "SVF HP",
+            FilterType::BandPass => "SVF BP",
+            FilterType::Notch => "SVF Notch",
+            FilterType::Formant => "Formant",
+            FilterType::CombPositive => "Comb +",
+            FilterType::CombNegative => "Comb -",
+        }
+    }
+}
+
+// Everything a filter model needs for one sample, worked out once and shared by
+// both channels of a voice.
+#[derive(Clone, Copy, Default)]
+pub struct FilterSettings {
+    pub filter_type: FilterType,
+    // 0 to 1, where resonant models begin to self-oscillate.
+    pub resonance: f32,
+    // Input gain into the saturator; see `saturate`.
+    pub drive: f32,
+    // Trapezoidal integrator gain for the cutoff, kept just below Nyquist.
+    pub g: f32,
+    peaks: [Peak; FORMANTS],
+    // Comb delay in samples.
+    comb_delay: f32,
+    pub per_sample: f32,
+}
+
+impl FilterSettings {
+    pub fn new(
+        filter_type: FilterType,
+        cutoff_hz: f32,
+        resonance: f32,
+        drive: f32,
+        vowel: f32,
+        comb_hz: f32,
+        per_sample: f32,
+    ) -> Self {
+        let resonance = resonance.clamp(0.0, 1.0);
+        FilterSettings {
+            filter_type,
+            resonance,
+            drive: drive.max(MIN_DRIVE),
+            g: integrator_gain(cutoff_hz, per_sample),
+            peaks: vowel_peaks(vowel, resonance, per_sample),
+            comb_delay: 1.0 / (comb_hz * per_sample),
+            per_sample,
+        }
+    }
+}
+
+fn integrator_gain(cutoff_hz: f32, per_sample: f32) -> f32 {
+    (PI * (cutoff_hz * per_sample).min(0.49)).tan()
+}
+
+// Below this the saturator is indistinguishable from a straight wire.
+const MIN_DRIVE: f32 = 1e-3;
+
+// tanh soft clipper, scaled so a full-scale input still comes out at full scale
+// whatever the drive. Small drive is nearly linear; large drive squashes everything
+// towards a square.
+fn saturate(input: f32, drive: f32) -> f32 {
+    (input * drive).tanh() / drive.tanh()
+}
+
+// A filter model that can sit in a voice's signal path.
+pub trait Filter {
+    fn reset(&mut self);
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32;
+}
+
+// Two-pole state-variable filter discretised with the trapezoidal rule (Simper's
+// formulation), so it stays stable under fast cutoff modulation. All four responses
+// come out of the same two integrators.
+#[derive(Clone, Copy, Default)]
+pub struct Svf {
+    ic1: f32,
+    ic2: f32,
+}
+
+impl Filter for Svf {
+    fn reset(&mut self) {
+        self.ic1 = 0.0;
+        self.ic2 = 0.0;
+    }
+
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32 {
+        // Damping from 2 (no resonance) down to a Q of 50.
+        let k = 2.0 - 1.98 * settings.resonance;
+        let (low, band, high) = self.tick(input, settings.g, k);
+        match settings.filter_type {
+            FilterType::HighPass => high,
+            // Scaled for unity gain at the peak however narrow it gets.
+            FilterType::BandPass => band * k,
+            FilterType::Notch => low + high,
+            _ => low,
+        }
+    }
+}
+
+impl Svf {
+    // Returns the low-, band- and high-pass outputs for integrator gain `g` and
+    // damping `k` (the reciprocal of Q).
+    fn tick(&mut self, input: f32, g: f32, k: f32) -> (f32, f32, f32) {
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        let v3 = input - self.ic2;
+        let band = a1 * self.ic1 + a2 * v3;
+        let low = self.ic2 + a2 * self.ic1 + a3 * v3;
+        self.ic1 = 2.0 * band - self.ic1;
+        self.ic2 = 2.0 * low - self.ic2;
+
+        (low, band, input - k * band - low)
+    }
+}
+
+const FORMANTS: usize = 3;
+
+pub const VOWELS: [&str; 5] = ["A", "E", "I", "O", "U"];
+
+// Centre frequency (Hz), bandwidth (Hz) and gain of the first three formants of each
+// vowel, from the sung bass voice tables in the Csound manual.
+const VOWEL_FORMANTS: [[(f32, f32, f32); FORMANTS]; 5] = [
+    [(800.0, 80.0, 1.0), (1150.0, 90.0, 0.5), (2900.0, 120.0, 0.025)],
+    [(350.0, 60.0, 1.0), (2000.0, 100.0, 0.1), (2800.0, 120.0, 0.18)],
+    [(270.0, 60.0, 1.0), (2140.0, 90.0, 0.25), (2950.0, 100.0, 0.05)],
+    [(450.0, 40.0, 1.0), (800.0, 80.0, 0.28), (2830.0, 100.0, 0.08)],
+    [(325.0, 50.0, 1.0), (700.0, 60.0, 0.16), (2700.0, 170.0, 0.018)],
+];
+
+#[derive(Clone, Copy, Default)]
+struct Peak {
+    g: f32,
+    k: f32,
+    gain: f32,
+}
+
+// `vowel` morphs A-E-I-O-U across 0 to 1; resonance narrows every peak.
+fn vowel_peaks(vowel: f32, resonance: f32, per_sample: f32) -> [Peak; FORMANTS] {
+    let position = vowel.clamp(0.0, 1.0) * (VOWEL_FORMANTS.len() - 1) as f32;
+    let index = (position as usize).min(VOWEL_FORMANTS.len() - 2);
+    let frac = position - index as f32;
+    std::array::from_fn(|i| {
+        let (freq0, width0, gain0) = VOWEL_FORMANTS[index][i];
+        let (freq1, width1, gain1) = VOWEL_FORMANTS[index + 1][i];
+        let freq = freq0 * (freq1 / freq0).powf(frac);
+        let width = (width0 + (width1 - width0) * frac) / (1.0 + 3.0 * resonance);
+        Peak {
+            g: integrator_gain(freq, per_sample),
+            k: width / freq,
+            gain: gain0 + (gain1 - gain0) * frac,
+        }
+    })
+}
+
+// Parallel band-passes tuned to the formants of a vowel; the cutoff plays no part.
+#[derive(Clone, Copy, Default)]
+pub struct Formant {
+    bands: [Svf; FORMANTS],
+}
+
+impl Filter for Formant {
+    fn reset(&mut self) {
+        self.bands.iter_mut().for_each(Svf::reset);
+    }
+
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32 {
+        let mut out = 0.0;
+        for (band, peak) in self.bands.iter_mut().zip(&settings.peaks) {
+            let (_, output, _) = band.tick(input, peak.g, peak.k);
+            out += output * peak.k * peak.gain;
+        }
+        out
+    }
+}
+
+// Longest comb delay; enough for ~11 Hz at 96 kHz.
+const MAX_COMB_DELAY: usize = 8192;
+
+// Feedback comb tuned to `comb_hz`, with a one-pole low-pass at the cutoff in the loop
+// so the ringing darkens as it decays. Positive feedback reinforces every harmonic of
+// the comb's pitch, negative only the odd ones, for a hollower tone an octave down.
+// Resonance sets the feedback.
+pub struct Comb {
+    buffer: Vec<f32>,
+    write: usize,
+    // Samples written since the last reset, up to the line's length. Anything older
+    // reads as silence, so a reset, which every note-on does, needn't clear the line.
+    filled: usize,
+    damping: f32,
+}
+
+impl Default for Comb {
+    fn default() -> Comb {
+        Comb {
+            buffer: vec![0.0; MAX_COMB_DELAY],
+            write: 0,
+            filled: 0,
+            damping: 0.0,
+        }
+    }
+}
+
+impl Comb {
+    // What was written `age` samples ago.
+    fn past(&self, age: usize) -> f32 {
+        if age == 0 || age > self.filled {
+            return 0.0;
+        }
+        self.buffer[(self.write + MAX_COMB_DELAY - age) % MAX_COMB_DELAY]
+    }
+}
+
+impl Filter for Comb {
+    fn reset(&mut self) {
+        self.filled = 0;
+        self.damping = 0.0;
+    }
+
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32 {
+        let delay = settings.comb_delay.clamp(1.0, (MAX_COMB_DELAY - 2) as f32);
+        let read = self.write as f32 + MAX_COMB_DELAY as f32 - delay;
+        let age = self.write + MAX_COMB_DELAY - read as usize;
+        let frac = read - read.floor();
+        let (y0, y1) = (self.past(age), self.past(age - 1));
+        let delayed = y0 + (y1 - y0) * frac;
+
+        let v = (delayed - self.damping) * settings.g / (1.0 + settings.g);
+        let damped = v + self.damping;
+        self.damping = damped + v;
+
+        let feedback = settings.resonance * 0.98;
+        let sign = if settings.filter_type == FilterType::CombNegative { -1.0 } else { 1.0 };
+        let out = input + sign * feedback * damped;
+        self.buffer[self.write] = out;
+        self.write = (self.write + 1) % MAX_COMB_DELAY;
+        self.filled = (self.filled + 1).min(MAX_COMB_DELAY);
+        // Unity gain at the comb's peaks.
+        out * (1.0 - feedback)
+    }
+}
+
+// How long a change of filter type takes to crossfade.
+const SWITCH_SECONDS: f32 = 0.005;
+
+// One instance of every filter model.
+#[derive(Default)]
+struct Models {
+    ladder: Ladder,
+    svf: Svf,
+    formant: Formant,
+    comb: Comb,
+}
+
+impl Models {
+    fn get(&mut self, filter_type: FilterType) -> &mut dyn Filter {
+        match filter_type {
+            FilterType::Ladder => &mut self.ladder,
+            FilterType::Formant => &mut self.formant,
+            FilterType::CombPositive | FilterType::CombNegative => &mut self.comb,
+            _ => &mut self.svf,
+        }
+    }
+}
+
+// The filter stage of one voice channel. Changing type crossfades from the old filter
+// to the new one, which starts from silence, rather than jumping between outputs. The
+// two sides of a crossfade run on separate sets of models, since several types share a
+// model and a model can only run once per sample.
+#[derive(Default)]
+pub struct VoiceFilter {
+    models: [Models; 2],
+    active: usize,
+    current: Option<FilterType>,
+    previous: FilterType,
+    // Share of the output still taken from `previous`, falling from 1 to 0.
+    fade: f32,
+}
+
+impl Filter for VoiceFilter {
+    fn reset(&mut self) {
+        for models in self.models.iter_mut() {
+            models.ladder.reset();
+            models.svf.reset();
+            models.formant.reset();
+            models.comb.reset();
+        }
+        self.current = None;
+        self.fade = 0.0;
+    }
+
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32 {
+        let input = saturate(input, settings.drive);
+
+        let filter_type = settings.filter_type;
+        match self.current {
+            Some(current) if current != filter_type => {
+                self.previous = current;
+                self.fade = 1.0;
+                self.active = 1 - self.active;
+                self.models[self.active].get(filter_type).reset();
+            }
+            _ => (),
+        }
+        self.current = Some(filter_type);
+
+        let out = self.models[self.active].get(filter_type).process(input, settings);
+        if self.fade <= 0.0 {
+            return out;
+        }
+        let previous = FilterSettings {
+            filter_type: self.previous,
+            ..*settings
+        };
+        let old = self.models[1 - self.active].get(self.previous).process(input, &previous);
+        let blended = out + (old - out) * self.fade;
+        self.fade -= settings.per_sample / SWITCH_SECONDS;
+        blended
+    }
+}
+
+// Corner of the DC blocker; low enough to leave the lowest notes alone.
+const DC_BLOCK_HZ: f32 = 10.0;
+
+// One-pole high-pass that takes out the offset asymmetric waveshapes, FM and PWM leave
+// on the output.
+#[derive(Clone, Copy, Default)]
+pub struct DcBlocker {
+    input: f32,
+    output: f32,
+}
+
+impl DcBlocker {
+    // Pole radius for `per_sample`; work it out once per block.
+    pub fn coefficient(per_sample: f32) -> f32 {
+        (-2.0 * PI * DC_BLOCK_HZ * per_sample).exp()
+    }
+
+    pub fn process(&mut self, input: f32, coefficient: f32) -> f32 {
+        self.output = input - self.input + coefficient * self.output;
+        self.input = input;
+        self.output
+    }
+}
@@ -0,0 +1,157 @@
+use std::f32::consts::PI;
+
+use vst::util::AtomicFloat;
+
+use crate::envelope::{
+    segment_seconds, Envelope, EnvelopeCurves, EnvelopeSettings,
+};
+use crate::sine;
+use crate::RustSynthParameters;
+
+pub const OPERATORS: usize = 4;
+
+// Phase deviation, in radians, produced by a modulator at full level.
+const MAX_MODULATION: f32 = 8.0;
+
+#[derive(Default)]
+pub struct FmOperatorParameters {
+    pub ratio: AtomicFloat,
+    pub level: AtomicFloat,
+    pub attack: AtomicFloat,
+    pub decay: AtomicFloat,
+    pub sustain: AtomicFloat,
+    pub release: AtomicFloat,
+}
+
+impl FmOperatorParameters {
+    pub fn envelope(&self, curves: EnvelopeCurves) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: 0.0,
+            attack: segment_seconds(self.attack.get()),
+            hold: 0.0,
+            decay: segment_seconds(self.decay.get()),
+            sustain: self.sustain.get(),
+            release: segment_seconds(self.release.get()),
+            curves,
+            loops: 0,
+        }
+    }
+}
+
+// Operators are numbered 1–4 in names but indexed 0–3 here. Every algorithm only lets
+// higher operators modulate lower ones, so evaluating from operator 4 down to 1 always
+// has the modulator outputs ready.
+pub struct Algorithm {
+    pub name: &'static str,
+    modulators: [&'static [usize]; OPERATORS],
+    carriers: &'static [usize],
+}
+
+pub const ALGORITHMS: [Algorithm; 6] = [
+    Algorithm {
+        name: "4>3>2>1",
+        modulators: [&[1], &[2], &[3], &[]],
+        carriers: &[0],
+    },
+    Algorithm {
+        name: "(4>3)+2>1",
+        modulators: [&[1, 2], &[], &[3], &[]],
+        carriers: &[0],
+    },
+    Algorithm {
+        name: "4>3, 2>1",
+        modulators: [&[1], &[], &[3], &[]],
+        carriers: &[0, 2],
+    },
+    Algorithm {
+        name: "4>(1,2,3)",
+        modulators: [&[3], &[3], &[3], &[]],
+        carriers: &[0, 1, 2],
+    },
+    Algorithm {
+        name: "4>3, 1, 2",
+        modulators: [&[], &[], &[3], &[]],
+        carriers: &[0, 1, 2],
+    },
+    Algorithm {
+        name: "1, 2, 3, 4",
+        modulators: [&[], &[], &[], &[]],
+        carriers: &[0, 1, 2, 3],
+    },
+];
+
+// What an FM voice plays for a control block: the algorithm, and each operator's
+// envelope, level and phase increment per sample.
+#[derive(Clone, Copy)]
+pub struct FmSettings {
+    algorithm: &'static Algorithm,
+    envelopes: [EnvelopeSettings; OPERATORS],
+    levels: [f32; OPERATORS],
+    incs: [f32; OPERATORS],
+}
+
+impl FmSettings {
+    pub fn new(params: &RustSynthParameters, freq: f32, per_sample: f32) -> FmSettings {
+        let curves = params.envelope_curves();
+        let operators = &params.fm_operators;
+        FmSettings {
+            algorithm: params.fm_algorithm(),
+            envelopes: std::array::from_fn(|op| operators[op].envelope(curves)),
+            levels: std::array::from_fn(|op| operators[op].level.get()),
+            incs: std::array::from_fn(|op| freq * params.fm_operator_ratio(op) * per_sample),
+        }
+    }
+}
+
+impl Default for FmSettings {
+    fn default() -> FmSettings {
+        FmSettings {
+            algorithm: &ALGORITHMS[0],
+            envelopes: [EnvelopeSettings::default(); OPERATORS],
+            levels: [0.0; OPERATORS],
+            incs: [0.0; OPERATORS],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FmVoice {
+    phases: [f32; OPERATORS],
+    envelopes: [Envelope; OPERATORS],
+}
+
+impl FmVoice {
+    pub fn reset(&mut self) {
+        self.phases = [0.0; OPERATORS];
+        self.envelopes.iter_mut().for_each(Envelope::trigger);
+    }
+
+    pub fn envelopes_mut(&mut self) -> &mut [Envelope; OPERATORS] {
+        &mut self.envelopes
+    }
+
+    pub fn release(&mut self) {
+        self.envelopes.iter_mut().for_each(Envelope::release);
+    }
+
+    // Done once every operator has finished its release.
+    pub fn finished(&self) -> bool {
+        self.envelopes.iter().all(Envelope::is_idle)
+    }
+
+    pub fn render(&mut self, settings: &FmSettings, per_sample: f32) -> f32 {
+        let algorithm = settings.algorithm;
+        let mut outputs = [0.0; OPERATORS];
+
+        for op in (0..OPERATORS).rev() {
+            let envelope = self.envelopes[op].next(&settings.envelopes[op], per_sample);
+            let modulation: f32 = algorithm.modulators[op].iter().map(|&m| outputs[m]).sum();
+            let phase = self.phases[op] + modulation * MAX_MODULATION / (2.0 * PI);
+            outputs[op] = sine::sine(phase) * settings.levels[op] * envelope;
+            self.phases[op] = (self.phases[op] + settings.incs[op]).fract();
+        }
+
+        let carriers = algorithm.carriers;
+        carriers.iter().map(|&c| outputs[c]).sum::<f32>() / carriers.len() as f32
+    }
+}
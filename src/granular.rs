@@ -0,0 +1,92 @@
+use std::f32::consts::PI;
+
+use crate::random::Random;
+use crate::sampler::Sample;
+
+const MAX_GRAINS: usize = 32;
+
+#[derive(Clone, Copy, Default)]
+pub struct GrainSettings {
+    pub size_seconds: f32,
+    pub density_hz: f32,
+    pub position: f32,
+    pub jitter_semitones: f32,
+    // Playback speed of an unjittered grain, in sample frames per output sample.
+    pub rate: f32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Grain {
+    active: bool,
+    position: f64,
+    rate: f64,
+    age: f32,
+    length: f32,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct GranularVoice {
+    grains: [Grain; MAX_GRAINS],
+    until_next: f32,
+    rng: Random,
+}
+
+impl GranularVoice {
+    pub fn reset(&mut self) {
+        self.grains = [Grain::default(); MAX_GRAINS];
+        self.until_next = 0.0;
+    }
+
+    pub fn next(
+        &mut self,
+        sample: &Sample,
+        settings: &GrainSettings,
+        sample_rate: f32,
+    ) -> (f32, f32) {
+        let length = (settings.size_seconds * sample_rate).max(1.0);
+
+        self.until_next -= 1.0;
+        if self.until_next <= 0.0 {
+            self.until_next += sample_rate / settings.density_hz;
+            self.spawn(sample, settings, length);
+        }
+
+        let last_frame = sample.len().saturating_sub(1) as f64;
+        let (mut left, mut right) = (0.0, 0.0);
+        for grain in self.grains.iter_mut().filter(|grain| grain.active) {
+            if grain.age >= grain.length || grain.position >= last_frame {
+                grain.active = false;
+                continue;
+            }
+            // Hann window over the grain's lifetime.
+            let window = 0.5 - 0.5 * (2.0 * PI * grain.age / grain.length).cos();
+            let (l, r) = sample.frame(grain.position);
+            left += l * window;
+            right += r * window;
+            grain.position += grain.rate;
+            grain.age += 1.0;
+        }
+
+        // Keep the level steady as more grains overlap.
+        let overlap = (settings.density_hz * settings.size_seconds).max(1.0);
+        let gain = 1.0 / overlap.sqrt();
+        (left * gain, right * gain)
+    }
+
+    fn spawn(&mut self, sample: &Sample, settings: &GrainSettings, length: f32) {
+        if sample.len() < 2 {
+            return;
+        }
+        if let Some(grain) = self.grains.iter_mut().find(|grain| !grain.active) {
+            let jitter = self.rng.bipolar() * settings.jitter_semitones;
+            let start = settings.position.clamp(0.0, 1.0) * (sample.len() - 1) as f32;
+            *grain = Grain {
+                active: true,
+                position: start as f64,
+                rate: (settings.rate * (jitter / 12.0).exp2()) as f64,
+                age: 0.0,
+                length,
+            };
+        }
+    }
+}
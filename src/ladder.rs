@@ -0,0 +1,43 @@
+use crate::filter::{Filter, FilterSettings};
+
+const STAGES: usize = 4;
+
+// Four one-pole low-passes in a resonant feedback loop, after the Moog transistor
+// ladder. The loop is solved without a unit delay (Zavalishin's zero-delay-feedback
+// form) so cutoff and resonance track correctly right up to self-oscillation, and the
+// feedback is soft-clipped so high resonance saturates rather than blowing up.
+#[derive(Clone, Copy, Default)]
+pub struct Ladder {
+    stages: [f32; STAGES],
+}
+
+impl Filter for Ladder {
+    fn reset(&mut self) {
+        self.stages = [0.0; STAGES];
+    }
+
+    fn process(&mut self, input: f32, settings: &FilterSettings) -> f32 {
+        let g = settings.g;
+        let k = settings.resonance * 4.0;
+        let gain = g / (1.0 + g);
+        let hold = 1.0 / (1.0 + g);
+
+        // Each stage's output is `gain * x + hold * state`, so the loop's output is a
+        // known multiple of its input plus what the states contribute.
+        let mut state_sum = 0.0;
+        for &state in &self.stages {
+            state_sum = state_sum * gain + hold * state;
+        }
+        let loop_gain = gain.powi(STAGES as i32);
+
+        // Make up some of the passband level that resonance takes away.
+        let drive = input * (1.0 + 0.5 * k);
+        let mut x = (drive - k * state_sum.tanh()) / (1.0 + k * loop_gain);
+        for state in self.stages.iter_mut() {
+            let v = (x - *state) * gain;
+            x = v + *state;
+            *state = x + v;
+        }
+        x
+    }
+}
@@ -0,0 +1,208 @@
+use std::f32::consts::PI;
+
+use crate::random::Random;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    SawUp,
+    SawDown,
+    Square,
+    SampleAndHold,
+    SmoothRandom,
+}
+
+impl LfoShape {
+    const ALL: [LfoShape; 7] = [
+        LfoShape::Sine,
+        LfoShape::Triangle,
+        LfoShape::SawUp,
+        LfoShape::SawDown,
+        LfoShape::Square,
+        LfoShape::SampleAndHold,
+        LfoShape::SmoothRandom,
+    ];
+
+    pub fn from_param(value: f32) -> LfoShape {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LfoShape::Sine => "Sine",
+            LfoShape::Triangle => "Triangle",
+            LfoShape::SawUp => "Saw Up",
+            LfoShape::SawDown => "Saw Down",
+            LfoShape::Square => "Square",
+            LfoShape::SampleAndHold => "S&H",
+            LfoShape::SmoothRandom => "Smooth Random",
+        }
+    }
+}
+
+// Note lengths a synced LFO cycle can take, in quarter notes, slowest first.
+pub const DIVISIONS: [(&str, f32); 18] = [
+    ("1/1 D", 6.0),
+    ("1/1", 4.0),
+    ("1/2 D", 3.0),
+    ("1/1 T", 8.0 / 3.0),
+    ("1/2", 2.0),
+    ("1/4 D", 1.5),
+    ("1/2 T", 4.0 / 3.0),
+    ("1/4", 1.0),
+    ("1/8 D", 0.75),
+    ("1/4 T", 2.0 / 3.0),
+    ("1/8", 0.5),
+    ("1/16 D", 0.375),
+    ("1/8 T", 1.0 / 3.0),
+    ("1/16", 0.25),
+    ("1/32 D", 0.1875),
+    ("1/16 T", 1.0 / 6.0),
+    ("1/32", 0.125),
+    ("1/32 T", 1.0 / 12.0),
+];
+
+// Free-running low-frequency oscillator shared by every voice, so vibrato stays in
+// step across a chord.
+#[derive(Default)]
+pub struct Lfo {
+    phase: f32,
+    // The random shapes pick a new value each cycle; smooth random glides to it from
+    // the last one.
+    random: f32,
+    previous_random: f32,
+    rng: Random,
+}
+
+impl Lfo {
+    // Returns the current value, from -1 to 1, and advances by `seconds`.
+    pub fn next(&mut self, shape: LfoShape, rate_hz: f32, seconds: f32) -> f32 {
+        let phase = self.phase;
+        let out = match shape {
+            LfoShape::Sine => (phase * 2.0 * PI).sin(),
+            // Starts at zero heading up, like the sine.
+            LfoShape::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+            LfoShape::SawUp => phase * 2.0 - 1.0,
+            LfoShape::SawDown => 1.0 - phase * 2.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => self.random,
+            LfoShape::SmoothRandom => {
+                let blend = 0.5 - 0.5 * (phase * PI).cos();
+                self.previous_random + (self.random - self.previous_random) * blend
+            }
+        };
+
+        self.phase += rate_hz * seconds;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.previous_random = self.random;
+            self.random = self.rng.bipolar();
+        }
+        out
+    }
+}
+
+// Clocked random steps, like a sample-and-hold fed with noise. `smooth` glides from
+// one step to the next over up to a whole clock period instead of jumping.
+#[derive(Default)]
+pub struct SampleAndHold {
+    clock: f32,
+    held: f32,
+    out: f32,
+    rng: Random,
+}
+
+impl SampleAndHold {
+    pub fn next(&mut self, rate_hz: f32, smooth: f32, seconds: f32) -> f32 {
+        self.clock += rate_hz * seconds;
+        if self.clock >= 1.0 {
+            self.clock = self.clock.fract();
+            self.held = self.rng.bipolar();
+        }
+
+        let glide = smooth.clamp(0.0, 1.0) / rate_hz;
+        if glide <= seconds {
+            self.out = self.held;
+        } else {
+            self.out += (self.held - self.out) * (1.0 - (-seconds / glide).exp());
+        }
+        self.out
+    }
+}
+
+pub const SEQUENCER_STEPS: usize = 16;
+
+// A loop of `SEQUENCER_STEPS` values played one per clock tick. `glide` is the fraction of
+// each step spent sliding in a straight line from the previous step's value.
+#[derive(Default)]
+pub struct StepSequencer {
+    step: usize,
+    phase: f32,
+}
+
+impl StepSequencer {
+    pub fn next(
+        &mut self,
+        steps: &[f32; SEQUENCER_STEPS],
+        rate_hz: f32,
+        glide: f32,
+        seconds: f32,
+    ) -> f32 {
+        let current = steps[self.step];
+        let previous = steps[(self.step + SEQUENCER_STEPS - 1) % SEQUENCER_STEPS];
+        let out = if self.phase < glide {
+            previous + (current - previous) * self.phase / glide
+        } else {
+            current
+        };
+
+        self.phase += rate_hz * seconds;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.step = (self.step + 1) % SEQUENCER_STEPS;
+        }
+        out
+    }
+}
+
+// A Lorenz attractor, for movement that never quite repeats. The state is integrated
+// `rate_hz` time units per second; the orbit swings between its two lobes roughly that
+// often.
+pub struct Chaos {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Default for Chaos {
+    fn default() -> Chaos {
+        // Anywhere off the origin falls onto the attractor.
+        Chaos { x: 0.1, y: 0.0, z: 0.0 }
+    }
+}
+
+impl Chaos {
+    const SIGMA: f32 = 10.0;
+    const RHO: f32 = 28.0;
+    const BETA: f32 = 8.0 / 3.0;
+
+    // Returns x, scaled to about -1 to 1, and advances by `seconds`.
+    pub fn next(&mut self, rate_hz: f32, seconds: f32) -> f32 {
+        let dt = rate_hz * seconds;
+        let dx = Self::SIGMA * (self.y - self.x);
+        let dy = self.x * (Self::RHO - self.z) - self.y;
+        let dz = self.x * self.y - Self::BETA * self.z;
+        self.x += dx * dt;
+        self.y += dy * dt;
+        self.z += dz * dt;
+        (self.x / 20.0).clamp(-1.0, 1.0)
+    }
+}
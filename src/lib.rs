@@ -1,46 +1,534 @@
 use vst::prelude::*;
+use vst::buffer::{Outputs, SendEventBuffer};
+use vst::event::{MidiEvent, SysExEvent};
+use vst::host::{Host, OpCode};
 use vst::plugin_main;
 use vst::util::AtomicFloat;
-use std::f32::consts::PI;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
+mod additive;
+mod arpeggiator;
+mod chord;
+mod denormal;
+mod descriptor;
+mod drift;
+mod drum;
+mod envelope;
+mod euclidean;
+mod filter;
+mod fm;
+mod granular;
+mod ladder;
+mod lfo;
+mod midi;
+mod modulation;
+mod noise;
+mod note_sequencer;
+mod organ;
+mod oscillator;
+mod oversample;
+mod pluck;
+mod program;
+mod random;
+mod sampler;
+mod sine;
+mod smoothing;
+mod state;
+mod strum;
+mod transport;
+mod sysex;
+mod voice;
+mod wavetable;
+
+use additive::{HarmonicProfile, CUSTOM_HARMONICS, MAX_HARMONICS};
+use arpeggiator::{ArpMode, ArpStep, Arpeggiator, MAX_OCTAVES};
+use chord::{ChordMemory, ChordMode, Shape};
+use denormal::FlushDenormals;
+use descriptor::{
+    descriptor, Range, BEND_RANGE, CHAOS_RATE, COMB_TUNE, CUTOFF, DRUM_DECAY, DRUM_TUNE,
+    FILTER_ENV_AMOUNT, FM_OPERATORS_START, GLIDE_TIME, GRAIN_DENSITY, GRAIN_JITTER, GRAIN_SIZE,
+    KEY_TRACK, LFO_DELAY, LFO_DEPTH, LFO_FADE_IN, LFO_RATE, MACROS_START, MIDI_LEARN,
+    MOD_ENV_AMOUNT, MOD_SLOTS_START, NOTE_STEPS_START, OSC2_FINE, PLUCK_DECAY, PULSE_WIDTH,
+    PWM_RATE, SAMPLE, SAMPLE_HOLD_AMOUNT, SAMPLE_HOLD_RATE, SAVE_PRESET, STRUM, SYNC_TUNE,
+    UNISON_DETUNE, WHEEL_AMOUNT,
+};
+use envelope::{segment_seconds, EnvelopeCurves, EnvelopeSettings, LOOP_FOREVER};
+use euclidean::{Euclidean, Hit, Rhythm, MAX_STEPS};
+use filter::DcBlocker;
+use fm::{FmOperatorParameters, ALGORITHMS, OPERATORS};
+use lfo::{
+    Chaos, Lfo, LfoShape, SampleAndHold, StepSequencer, DIVISIONS, SEQUENCER_STEPS,
+};
+use midi::{Message, NoteOutput, Parser};
+use modulation::{
+    Destination, MacroParameters, ModSlotParameters, SharedSources, Smoother, MACROS,
+    MACRO_TARGETS, MOD_SLOTS,
+};
+use note_sequencer::{NoteSequencer, NoteStep, NoteStepParameters, NOTE_STEPS};
+use organ::DRAWBARS;
+use oversample::{Decimator, Quality, MAX_FACTOR};
+use program::{Bank, PROGRAMS};
+use random::Random;
+use sampler::Sample;
+use smoothing::ParameterSmoother;
+use strum::Strum;
+use transport::Transport;
+use sysex::SysEx;
+use voice::{Engine, VoiceManager, VoiceMode, CONTROL_BLOCK, MAX_UNISON, MAX_VOICES, VECTOR_SLOTS};
+
+// Longest timing offset Humanize adds, in seconds, and the most it moves velocity.
+const HUMANIZE_TIME: f32 = 0.02;
+const HUMANIZE_VELOCITY: f32 = 20.0;
+
+// Seconds the output takes to come back up after the host resumes the plugin.
+const RESUME_FADE: f32 = 0.005;
+
+// Seconds for channel pressure to settle, long enough to hide the 7-bit steps.
+const AFTERTOUCH_SMOOTHING: f32 = 0.02;
+// Seconds for the output level to follow a Volume change without zipper noise.
+const VOLUME_SMOOTHING: f32 = 0.01;
+// Range of the Volume knob, in decibels, above its silent bottom end.
+const VOLUME: Range = Range::linear(-60.0, 6.0);
+
+const PARAMETERS: i32 = 287;
+const PRESETS: i32 = PROGRAMS as i32;
+// Events queued per block; any beyond this are applied at once rather than allocating.
+const MAX_PENDING_EVENTS: usize = 1024;
+// Bindings by CC number; controllers 120 and up are channel mode messages.
+const LEARNABLE_CCS: usize = 120;
+const UNBOUND: i32 = -1;
+
+const PITCH_BEND_SENSITIVITY: (u8, u8) = (0, 0);
+// Deselects any RPN so stray data entry does nothing.
+const NULL_RPN: (u8, u8) = (127, 127);
+
+const FM_RATIOS: [f32; 19] = [
+    0.25, 0.5, 1.0, 1.5, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0,
+    15.0, 16.0,
+];
+
 struct RustSynth {
+    host: HostCallback,
     sample_rate: f32,
-    time: f32,
-    note: u8,
-    note_on: bool,
+    transport: Transport,
+    // Output level, from 0 to 1, rising after a resume.
+    resume_gain: f32,
+    // Oversampling factor the decimator's state was built at.
+    quality: Quality,
+    decimator: Decimator,
+    // Left and right.
+    dc_blockers: [DcBlocker; 2],
+    smoother: ParameterSmoother,
+    // Volume is smoothed every sample rather than every control block.
+    volume_smoother: Smoother,
+    voices: VoiceManager,
+    lfo: Lfo,
+    sample_hold: SampleAndHold,
+    sequencer: StepSequencer,
+    chaos: Chaos,
+    arpeggiator: Arpeggiator,
+    note_sequencer: NoteSequencer,
+    euclidean: Euclidean,
+    chords: ChordMemory,
+    strum: Strum,
+    // Draws the humanize nudges.
+    rng: Random,
+    // Where keys went as of the last block.
+    pattern: Pattern,
+    // Latest controller positions: pitch bend from -1 to 1, the rest from 0 to 1.
+    pitch_bend: f32,
+    mod_wheel: f32,
+    aftertouch: f32,
+    aftertouch_smoother: Smoother,
+    midi: Parser,
+    // MIDI from `process_events`, waiting for its sample in the next block, as
+    // (delta frames, bytes).
+    pending_events: Vec<(i32, [u8; 3])>,
+    // Registered parameter number selected by CC101 and CC100, as (MSB, LSB).
+    rpn: (u8, u8),
+    // Output mode as of the last block, to notice it changing under held notes.
+    note_output: NoteOutput,
+    // Sample in the block being processed, to stamp outgoing MIDI.
+    frame: i32,
+    midi_out: Vec<MidiEvent>,
+    // A patch dump asked for over SysEx, sent from the next `process` call.
+    dump_requested: bool,
+    sysex_out: Vec<u8>,
+    send_buffer: SendEventBuffer,
     params: Arc<RustSynthParameters>,
 }
 
-struct RustSynthParameters {
+// Every parameter's value, and the sample the Sample parameter names: all a parameter's
+// text reads, so text can be read from a copy without the rest of the plugin's state.
+struct Patch {
     volume: AtomicFloat,
     attack: AtomicFloat,
     decay: AtomicFloat,
     sustain: AtomicFloat,
     release: AtomicFloat,
+    waveform: AtomicFloat,
+    wt_position: AtomicFloat,
+    wt_interpolation: AtomicFloat,
+    osc2_waveform: AtomicFloat,
+    osc2_coarse: AtomicFloat,
+    osc2_fine: AtomicFloat,
+    osc_mix: AtomicFloat,
+    sub_shape: AtomicFloat,
+    sub_octave: AtomicFloat,
+    sub_level: AtomicFloat,
+    noise_color: AtomicFloat,
+    noise_level: AtomicFloat,
+    sync: AtomicFloat,
+    sync_tune: AtomicFloat,
+    pulse_width: AtomicFloat,
+    pwm_depth: AtomicFloat,
+    pwm_rate: AtomicFloat,
+    pwm_source: AtomicFloat,
+    fm_amount: AtomicFloat,
+    fm_ratio: AtomicFloat,
+    engine: AtomicFloat,
+    fm_algorithm: AtomicFloat,
+    fm_operators: [FmOperatorParameters; OPERATORS],
+    harmonics: AtomicFloat,
+    harmonic_profile: AtomicFloat,
+    harmonic_levels: [AtomicFloat; CUSTOM_HARMONICS],
+    pluck_decay: AtomicFloat,
+    pluck_brightness: AtomicFloat,
+    pd_amount: AtomicFloat,
+    unison: AtomicFloat,
+    unison_detune: AtomicFloat,
+    stereo_spread: AtomicFloat,
+    ring_mod: AtomicFloat,
+    sample: Mutex<Option<Arc<Sample>>>,
+    sample_start: AtomicFloat,
+    sample_loop: AtomicFloat,
+    grain_size: AtomicFloat,
+    grain_density: AtomicFloat,
+    grain_position: AtomicFloat,
+    grain_jitter: AtomicFloat,
+    drum_tune: AtomicFloat,
+    drum_decay: AtomicFloat,
+    drawbars: [AtomicFloat; DRAWBARS],
+    key_click: AtomicFloat,
+    vector_waves: [AtomicFloat; VECTOR_SLOTS],
+    vector_x: AtomicFloat,
+    vector_y: AtomicFloat,
+    drift: AtomicFloat,
+    cutoff: AtomicFloat,
+    resonance: AtomicFloat,
+    filter_type: AtomicFloat,
+    filter_attack: AtomicFloat,
+    filter_decay: AtomicFloat,
+    filter_sustain: AtomicFloat,
+    filter_release: AtomicFloat,
+    filter_env_amount: AtomicFloat,
+    key_track: AtomicFloat,
+    drive: AtomicFloat,
+    vowel: AtomicFloat,
+    comb_tune: AtomicFloat,
+    attack_curve: AtomicFloat,
+    decay_curve: AtomicFloat,
+    release_curve: AtomicFloat,
+    delay: AtomicFloat,
+    hold: AtomicFloat,
+    filter_delay: AtomicFloat,
+    filter_hold: AtomicFloat,
+    retrigger: AtomicFloat,
+    filter_loop: AtomicFloat,
+    velocity_to_envelope: AtomicFloat,
+    velocity_to_attack: AtomicFloat,
+    mod_attack: AtomicFloat,
+    mod_decay: AtomicFloat,
+    mod_sustain: AtomicFloat,
+    mod_release: AtomicFloat,
+    mod_env_amount: AtomicFloat,
+    mod_env_destination: AtomicFloat,
+    lfo_rate: AtomicFloat,
+    lfo_depth: AtomicFloat,
+    lfo_shape: AtomicFloat,
+    lfo_sync: AtomicFloat,
+    lfo_delay: AtomicFloat,
+    lfo_fade: AtomicFloat,
+    sample_hold_rate: AtomicFloat,
+    sample_hold_smooth: AtomicFloat,
+    sample_hold_amount: AtomicFloat,
+    sample_hold_destination: AtomicFloat,
+    mod_slots: [ModSlotParameters; MOD_SLOTS],
+    macros: [MacroParameters; MACROS],
+    sequencer_rate: AtomicFloat,
+    sequencer_glide: AtomicFloat,
+    sequencer_steps: [AtomicFloat; SEQUENCER_STEPS],
+    chaos_rate: AtomicFloat,
+    chaos_amount: AtomicFloat,
+    bend_range: AtomicFloat,
+    mod_wheel_destination: AtomicFloat,
+    mod_wheel_amount: AtomicFloat,
+    mpe: AtomicFloat,
+    midi_learn: AtomicFloat,
+    midi_channel: AtomicFloat,
+    velocity_curve: AtomicFloat,
+    voice_mode: AtomicFloat,
+    note_priority: AtomicFloat,
+    glide: AtomicFloat,
+    glide_time: AtomicFloat,
+    steal_policy: AtomicFloat,
+    max_voices: AtomicFloat,
+    note_output: AtomicFloat,
+    arp: AtomicFloat,
+    arp_mode: AtomicFloat,
+    arp_octaves: AtomicFloat,
+    arp_rate: AtomicFloat,
+    arp_gate: AtomicFloat,
+    note_seq: AtomicFloat,
+    note_seq_rate: AtomicFloat,
+    note_steps: [NoteStepParameters; NOTE_STEPS],
+    euclid: AtomicFloat,
+    euclid_rate: AtomicFloat,
+    euclid_steps: AtomicFloat,
+    euclid_pulses: AtomicFloat,
+    euclid_rotation: AtomicFloat,
+    euclid_gate: AtomicFloat,
+    chord_mode: AtomicFloat,
+    chord_learn: AtomicFloat,
+    strum: AtomicFloat,
+    humanize: AtomicFloat,
+    transport_reset: AtomicFloat,
+    quality: AtomicFloat,
+}
+
+struct RustSynthParameters {
+    patch: Patch,
+    // A `Shape`'s bits. Like the CC bindings, not a host parameter, but saved with the
+    // plugin's state.
+    learned_chord: AtomicU32,
+    // Parameter index each CC drives, or `UNBOUND`.
+    cc_bindings: [AtomicI32; LEARNABLE_CCS],
+    // The host's program slots; the live values above belong to the current one.
+    programs: Bank,
 }
 
 impl Default for RustSynth {
     fn default() -> RustSynth {
+        // Build the shared tables here rather than on the first audio callback.
+        wavetable::builtin();
+
         RustSynth {
+            host: HostCallback::default(),
             sample_rate: 44100.0,
-            time: 0.0,
-            note: 0,
-            note_on: false,
-            params: Arc::new(RustSynthParameters {
-                volume: AtomicFloat::new(0.5),
-                attack: AtomicFloat::new(0.01),
-                decay: AtomicFloat::new(0.1),
-                sustain: AtomicFloat::new(0.5),
-                release: AtomicFloat::new(0.1),
-            }),
+            transport: Transport::default(),
+            resume_gain: 1.0,
+            quality: Quality::Normal,
+            decimator: Decimator::default(),
+            dc_blockers: [DcBlocker::default(); 2],
+            smoother: ParameterSmoother::default(),
+            volume_smoother: Smoother::default(),
+            voices: VoiceManager::default(),
+            lfo: Lfo::default(),
+            sample_hold: SampleAndHold::default(),
+            sequencer: StepSequencer::default(),
+            chaos: Chaos::default(),
+            arpeggiator: Arpeggiator::default(),
+            note_sequencer: NoteSequencer::default(),
+            euclidean: Euclidean::default(),
+            chords: ChordMemory::default(),
+            strum: Strum::default(),
+            rng: Random::default(),
+            pattern: Pattern::Off,
+            pitch_bend: 0.0,
+            mod_wheel: 0.0,
+            aftertouch: 0.0,
+            aftertouch_smoother: Smoother::default(),
+            rpn: NULL_RPN,
+            midi: Parser::default(),
+            pending_events: Vec::with_capacity(MAX_PENDING_EVENTS),
+            note_output: NoteOutput::Audio,
+            frame: 0,
+            midi_out: Vec::with_capacity(MAX_PENDING_EVENTS),
+            dump_requested: false,
+            sysex_out: Vec::with_capacity(sysex::dump_len(PARAMETERS as usize)),
+            send_buffer: SendEventBuffer::new(MAX_PENDING_EVENTS),
+            params: Arc::default(),
+        }
+    }
+}
+
+// The live values are the current program's, so they're read and set on the parameters
+// as they are on the patch.
+impl Deref for RustSynthParameters {
+    type Target = Patch;
+
+    fn deref(&self) -> &Patch {
+        &self.patch
+    }
+}
+
+impl Default for Patch {
+    // Every host parameter starts where its descriptor says.
+    fn default() -> Patch {
+        let patch = Patch {
+            volume: AtomicFloat::default(),
+            attack: AtomicFloat::default(),
+            decay: AtomicFloat::default(),
+            sustain: AtomicFloat::default(),
+            release: AtomicFloat::default(),
+            waveform: AtomicFloat::default(),
+            wt_position: AtomicFloat::default(),
+            wt_interpolation: AtomicFloat::default(),
+            osc2_waveform: AtomicFloat::default(),
+            osc2_coarse: AtomicFloat::default(),
+            osc2_fine: AtomicFloat::default(),
+            osc_mix: AtomicFloat::default(),
+            sub_shape: AtomicFloat::default(),
+            sub_octave: AtomicFloat::default(),
+            sub_level: AtomicFloat::default(),
+            noise_color: AtomicFloat::default(),
+            noise_level: AtomicFloat::default(),
+            sync: AtomicFloat::default(),
+            sync_tune: AtomicFloat::default(),
+            pulse_width: AtomicFloat::default(),
+            pwm_depth: AtomicFloat::default(),
+            pwm_rate: AtomicFloat::default(),
+            pwm_source: AtomicFloat::default(),
+            fm_amount: AtomicFloat::default(),
+            fm_ratio: AtomicFloat::default(),
+            engine: AtomicFloat::default(),
+            fm_algorithm: AtomicFloat::default(),
+            fm_operators: Default::default(),
+            harmonics: AtomicFloat::default(),
+            harmonic_profile: AtomicFloat::default(),
+            harmonic_levels: Default::default(),
+            pluck_decay: AtomicFloat::default(),
+            pluck_brightness: AtomicFloat::default(),
+            pd_amount: AtomicFloat::default(),
+            unison: AtomicFloat::default(),
+            unison_detune: AtomicFloat::default(),
+            stereo_spread: AtomicFloat::default(),
+            ring_mod: AtomicFloat::default(),
+            sample: Mutex::new(None),
+            sample_start: AtomicFloat::default(),
+            sample_loop: AtomicFloat::default(),
+            grain_size: AtomicFloat::default(),
+            grain_density: AtomicFloat::default(),
+            grain_position: AtomicFloat::default(),
+            grain_jitter: AtomicFloat::default(),
+            drum_tune: AtomicFloat::default(),
+            drum_decay: AtomicFloat::default(),
+            drawbars: Default::default(),
+            key_click: AtomicFloat::default(),
+            vector_waves: Default::default(),
+            vector_x: AtomicFloat::default(),
+            vector_y: AtomicFloat::default(),
+            drift: AtomicFloat::default(),
+            cutoff: AtomicFloat::default(),
+            resonance: AtomicFloat::default(),
+            filter_type: AtomicFloat::default(),
+            filter_attack: AtomicFloat::default(),
+            filter_decay: AtomicFloat::default(),
+            filter_sustain: AtomicFloat::default(),
+            filter_release: AtomicFloat::default(),
+            filter_env_amount: AtomicFloat::default(),
+            key_track: AtomicFloat::default(),
+            drive: AtomicFloat::default(),
+            vowel: AtomicFloat::default(),
+            comb_tune: AtomicFloat::default(),
+            attack_curve: AtomicFloat::default(),
+            decay_curve: AtomicFloat::default(),
+            release_curve: AtomicFloat::default(),
+            delay: AtomicFloat::default(),
+            hold: AtomicFloat::default(),
+            filter_delay: AtomicFloat::default(),
+            filter_hold: AtomicFloat::default(),
+            retrigger: AtomicFloat::default(),
+            filter_loop: AtomicFloat::default(),
+            velocity_to_envelope: AtomicFloat::default(),
+            velocity_to_attack: AtomicFloat::default(),
+            mod_attack: AtomicFloat::default(),
+            mod_decay: AtomicFloat::default(),
+            mod_sustain: AtomicFloat::default(),
+            mod_release: AtomicFloat::default(),
+            mod_env_amount: AtomicFloat::default(),
+            mod_env_destination: AtomicFloat::default(),
+            lfo_rate: AtomicFloat::default(),
+            lfo_depth: AtomicFloat::default(),
+            lfo_shape: AtomicFloat::default(),
+            lfo_sync: AtomicFloat::default(),
+            lfo_delay: AtomicFloat::default(),
+            lfo_fade: AtomicFloat::default(),
+            sample_hold_rate: AtomicFloat::default(),
+            sample_hold_smooth: AtomicFloat::default(),
+            sample_hold_amount: AtomicFloat::default(),
+            sample_hold_destination: AtomicFloat::default(),
+            mod_slots: Default::default(),
+            macros: Default::default(),
+            sequencer_rate: AtomicFloat::default(),
+            sequencer_glide: AtomicFloat::default(),
+            sequencer_steps: Default::default(),
+            chaos_rate: AtomicFloat::default(),
+            chaos_amount: AtomicFloat::default(),
+            bend_range: AtomicFloat::default(),
+            mod_wheel_destination: AtomicFloat::default(),
+            mod_wheel_amount: AtomicFloat::default(),
+            mpe: AtomicFloat::default(),
+            midi_learn: AtomicFloat::default(),
+            midi_channel: AtomicFloat::default(),
+            velocity_curve: AtomicFloat::default(),
+            voice_mode: AtomicFloat::default(),
+            note_priority: AtomicFloat::default(),
+            glide: AtomicFloat::default(),
+            glide_time: AtomicFloat::default(),
+            steal_policy: AtomicFloat::default(),
+            max_voices: AtomicFloat::default(),
+            note_output: AtomicFloat::default(),
+            arp: AtomicFloat::default(),
+            arp_mode: AtomicFloat::default(),
+            arp_octaves: AtomicFloat::default(),
+            arp_rate: AtomicFloat::default(),
+            arp_gate: AtomicFloat::default(),
+            note_seq: AtomicFloat::default(),
+            note_seq_rate: AtomicFloat::default(),
+            note_steps: Default::default(),
+            euclid: AtomicFloat::default(),
+            euclid_rate: AtomicFloat::default(),
+            euclid_steps: AtomicFloat::default(),
+            euclid_pulses: AtomicFloat::default(),
+            euclid_rotation: AtomicFloat::default(),
+            euclid_gate: AtomicFloat::default(),
+            chord_mode: AtomicFloat::default(),
+            chord_learn: AtomicFloat::default(),
+            strum: AtomicFloat::default(),
+            humanize: AtomicFloat::default(),
+            transport_reset: AtomicFloat::default(),
+            quality: AtomicFloat::default(),
+        };
+        for (index, descriptor) in (0..).zip(descriptor::table()) {
+            patch.set(index, descriptor.default);
+        }
+        patch
+    }
+}
+
+impl Default for RustSynthParameters {
+    fn default() -> RustSynthParameters {
+        RustSynthParameters {
+            patch: Patch::default(),
+            learned_chord: AtomicU32::new(Shape::MAJOR.0),
+            cc_bindings: std::array::from_fn(|_| AtomicI32::new(UNBOUND)),
+            programs: Bank::default(),
         }
     }
 }
 
 impl Plugin for RustSynth {
-    fn new(_host: HostCallback) -> Self {
-        Default::default()
+    fn new(host: HostCallback) -> Self {
+        let synth = RustSynth {
+            host,
+            ..Default::default()
+        };
+        synth.params.load_bank_presets();
+        synth
     }
 
     fn get_info(&self) -> Info {
@@ -51,52 +539,112 @@ impl Plugin for RustSynth {
             category: Category::Synth,
             inputs: 0,
             outputs: 2,
-            parameters: 5,
-            initial_delay: 0,
+            midi_inputs: 1,
+            midi_outputs: 1,
+            presets: PRESETS,
+            parameters: PARAMETERS,
+            initial_delay: self.latency(),
+            f64_precision: true,
+            // The sample, learned chord and CC bindings aren't parameters, so the host saves
+            // the plugin's own state instead.
+            preset_chunks: true,
             ..Default::default()
         }
     }
 
-    fn init(&mut self) {
-        self.sample_rate = 44100.0;
+    // Envelopes, filters, oscillators and clocks all work from `time_per_sample` as they
+    // go, so nothing else needs redoing. Hosts only change the rate while suspended, and
+    // suspending has already cleared the voices.
+    fn set_sample_rate(&mut self, rate: f32) {
+        if rate > 0.0 {
+            self.sample_rate = rate;
+        }
+    }
+
+    // The host stops calling `process` from here until `resume`, so there's no playing
+    // out a fade; instead nothing is left to pick up where it stopped. Voices, filters,
+    // patterns and anything queued start again from nothing.
+    fn suspend(&mut self) {
+        self.stop_patterns();
+        if self.note_output.sends() {
+            self.send_all_notes_off();
+        }
+        self.voices = VoiceManager::default();
+        self.decimator.reset();
+        self.dc_blockers = [DcBlocker::default(); 2];
+        self.smoother.reset();
+        self.chords.clear();
+        self.strum.clear();
+        self.pending_events.clear();
+        self.midi = Parser::default();
+        self.aftertouch_smoother = Smoother::default();
+    }
+
+    fn resume(&mut self) {
+        self.resume_gain = 0.0;
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
         let samples = buffer.samples();
         let (_, mut outputs) = buffer.split();
-        let output_count = outputs.len();
-        let per_sample = self.time_per_sample();
-
-        for sample_idx in 0..samples {
-            if self.note_on {
-                let wave = self.generate_wave();
-                let envelope = self.apply_envelope();
-                let out = wave * envelope * self.params.volume.get();
+        self.render(samples, &mut outputs);
+    }
 
-                for buf_idx in 0..output_count {
-                    let buff = outputs.get_mut(buf_idx);
-                    buff[sample_idx] = out;
-                }
-            }
-            self.time += per_sample;
-        }
+    fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
+        let samples = buffer.samples();
+        let (_, mut outputs) = buffer.split();
+        self.render(samples, &mut outputs);
     }
 
     fn process_events(&mut self, events: &Events) {
         for event in events.events() {
             match event {
-                Event::Midi(ev) => {
-                    match ev.data[0] {
-                        128 => self.note_off(ev.data[1]),
-                        144 => self.note_on(ev.data[1], ev.data[2]),
-                        _ => (),
-                    }
+                Event::Midi(ev) if self.pending_events.len() < MAX_PENDING_EVENTS => {
+                    // Hosts send events in time order, but don't rely on it. Events at the
+                    // same frame keep their order.
+                    let index = self
+                        .pending_events
+                        .iter()
+                        .rposition(|&(delta, _)| delta <= ev.delta_frames)
+                        .map_or(0, |index| index + 1);
+                    self.pending_events.insert(index, (ev.delta_frames, ev.data));
                 }
-                _ => (),
+                Event::Midi(ev) => self.midi_message(ev.data),
+                Event::SysEx(ev) => self.sysex_message(ev.payload),
+                _ => {}
             }
         }
     }
 
+    fn can_do(&self, can_do: CanDo) -> Supported {
+        match can_do {
+            CanDo::ReceiveEvents
+            | CanDo::ReceiveMidiEvent
+            | CanDo::ReceiveSysExEvent
+            | CanDo::SendEvents
+            | CanDo::SendMidiEvent => Supported::Yes,
+            _ => Supported::Maybe,
+        }
+    }
+
+    // Samples a note rings on for after its key is let go, so a bounce or freeze doesn't
+    // cut it short: the amp release, or longer where the engine ends notes its own way.
+    // Hosts read 0 as "don't know", so no tail at all is 1.
+    fn get_tail_size(&self) -> isize {
+        let params = &self.params;
+        let engine = match Engine::from_param(params.engine.get()) {
+            // FM voices end with their operators, whatever the amp envelope does.
+            Engine::Fm => (params.fm_operators.iter())
+                .map(|op| segment_seconds(op.release.get()))
+                .fold(0.0, f32::max),
+            Engine::Drum => drum::longest_ring(params.drum_decay_scale()),
+            Engine::Pluck => params.pluck_decay_seconds(),
+            _ => 0.0,
+        };
+        let seconds = segment_seconds(params.release.get()).max(engine);
+        (seconds * self.sample_rate).ceil().max(1.0) as isize
+    }
+
     fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
         Arc::clone(&self.params) as Arc<dyn PluginParameters>
     }
@@ -107,96 +655,1290 @@ impl RustSynth {
         1.0 / self.sample_rate
     }
 
-    fn note_on(&mut self, note: u8, _velocity: u8) {
-        self.note = note;
-        self.note_on = true;
-        self.time = 0.0;
-    }
+    // The whole of `process`, for either sample width. Synthesis runs in `f32` and only
+    // the finished samples are widened.
+    fn render<T: From<f32>>(&mut self, samples: usize, outputs: &mut Outputs<T>) {
+        let _denormals = FlushDenormals::new();
+        let output_count = outputs.len();
+        let per_sample = self.time_per_sample();
+
+        if self.transport.update(&self.host) && self.params.transport_reset_enabled() {
+            self.restart_clocks();
+        }
+        let lfo_rate = self.params.lfo_rate_hz(&self.transport);
+        let sequencer_rate = self.params.sequencer_rate_hz(&self.transport);
+        let steps = self.params.sequencer_values();
+        let chaos_rate = self.params.chaos_rate_hz();
+        let chaos_amount = self.params.chaos_amount.get();
+        let arp_mode = self.params.arp_mode();
+        let arp_octaves = self.params.arp_octaves();
+        let arp_rate = self.params.arp_rate_hz(&self.transport);
+        let arp_gate = self.params.arp_gate();
+        let note_seq_rate = self.params.note_seq_rate_hz(&self.transport);
+        let note_steps = self.params.note_steps();
+        let rhythm = self.params.euclid_rhythm();
+        let euclid_rate = self.params.euclid_rate_hz(&self.transport);
+        let euclid_gate = self.params.euclid_gate();
+        let quality = self.params.quality();
+        if quality != self.quality {
+            self.quality = quality;
+            self.decimator.reset();
+            self.report_latency();
+        }
+        let factor = quality.factor();
+        let voice_time = per_sample / factor as f32;
+        let dc_coefficient = DcBlocker::coefficient(per_sample);
+
+        // The sample is swapped in from the UI thread; never wait for it here.
+        if let Ok(sample) = self.params.sample.try_lock() {
+            self.voices.set_sample(sample.clone());
+        }
+        self.voices.enforce_limit(&self.params);
+        self.set_note_output(self.params.note_output());
+        self.set_pattern(self.params.pattern());
+        if self.dump_requested {
+            self.send_dump();
+        }
+
+        // The block is worked in short control blocks. Each one starts at a MIDI event if
+        // one falls inside, so events still land on their own sample.
+        let mut next_event = 0;
+        let mut block_start = 0;
+        while block_start < samples {
+            self.frame = block_start as i32;
+            while let Some(&(delta, data)) = self.pending_events.get(next_event) {
+                if delta > block_start as i32 {
+                    break;
+                }
+                self.midi_message(data);
+                next_event += 1;
+            }
+            let next_delta = self.pending_events.get(next_event).map(|&(delta, _)| delta as usize);
+            let block_end = (block_start + CONTROL_BLOCK)
+                .min(next_delta.unwrap_or(samples))
+                .min(samples);
+            let block_time = (block_end - block_start) as f32 * per_sample;
 
-    fn note_off(&mut self, note: u8) {
-        if self.note == note {
-            self.note_on = false;
+            let shape = LfoShape::from_param(self.params.lfo_shape.get());
+            let shared = SharedSources {
+                lfo: self.lfo.next(shape, lfo_rate, block_time),
+                sample_hold: self.sample_hold.next(
+                    self.params.sample_hold_rate_hz(),
+                    self.params.sample_hold_smooth.get(),
+                    block_time,
+                ),
+                sequencer: self.sequencer.next(
+                    &steps,
+                    sequencer_rate,
+                    self.params.sequencer_glide.get(),
+                    block_time,
+                ),
+                chaos: self.chaos.next(chaos_rate, block_time) * chaos_amount,
+                pitch_bend: self.pitch_bend,
+                mod_wheel: self.mod_wheel,
+                aftertouch: self.aftertouch_smoother.next(
+                    self.aftertouch,
+                    AFTERTOUCH_SMOOTHING,
+                    block_time,
+                ),
+            };
+            let smoothed = self.smoother.next(&self.params, block_time);
+
+            for sample_idx in block_start..block_end {
+                self.frame = sample_idx as i32;
+                while let Some(event) = self.strum.next() {
+                    match event.velocity {
+                        Some(velocity) => self.pattern_on(event.channel, event.note, velocity),
+                        None => self.pattern_off(event.channel, event.note),
+                    }
+                }
+                self.strum.advance();
+                match self.pattern {
+                    Pattern::Off => (),
+                    Pattern::Arpeggiator => {
+                        let step = self.arpeggiator.next(
+                            arp_mode,
+                            arp_octaves,
+                            arp_rate,
+                            arp_gate,
+                            per_sample,
+                        );
+                        self.play_step(step);
+                    }
+                    Pattern::Sequencer => {
+                        let step =
+                            self.note_sequencer.next(&note_steps, note_seq_rate, per_sample);
+                        self.play_step(step);
+                    }
+                    Pattern::Euclidean => {
+                        let hit =
+                            self.euclidean.next(rhythm, euclid_rate, euclid_gate, per_sample);
+                        self.play_hit(hit);
+                    }
+                }
+
+                let mut frames = [(0.0, 0.0); MAX_FACTOR];
+                for (i, frame) in frames[..factor].iter_mut().enumerate() {
+                    let control = (sample_idx == block_start && i == 0).then_some(block_time);
+                    *frame =
+                        self.voices.render(&self.params, voice_time, &shared, &smoothed, control);
+                }
+                let (left, right) = self.decimator.process(&frames[..factor]);
+                let left = self.dc_blockers[0].process(left, dc_coefficient);
+                let right = self.dc_blockers[1].process(right, dc_coefficient);
+                let volume = self.params.volume_gain();
+                let volume = self.volume_smoother.next(volume, VOLUME_SMOOTHING, per_sample);
+                let volume = volume * self.resume_gain;
+                self.resume_gain = (self.resume_gain + per_sample / RESUME_FADE).min(1.0);
+
+                for buf_idx in 0..output_count {
+                    let buff = outputs.get_mut(buf_idx);
+                    let out = if buf_idx % 2 == 0 { left } else { right };
+                    buff[sample_idx] = T::from(out * volume);
+                }
+            }
+            block_start = block_end;
+        }
+        // Anything stamped past the end of the block still happens, just late.
+        for index in next_event..self.pending_events.len() {
+            self.midi_message(self.pending_events[index].1);
         }
+        self.pending_events.clear();
+        self.frame = 0;
+        self.transport.advance(samples as f32 * per_sample);
+        if !self.midi_out.is_empty() {
+            self.send_buffer.send_events(&self.midi_out, &mut self.host);
+            self.midi_out.clear();
+        }
+    }
+
+    // Samples of delay the host should make up for. Nothing in the signal path looks
+    // ahead, so it's only the oversampling filters' delay.
+    fn latency(&self) -> i32 {
+        self.params.quality().latency()
     }
 
-    fn generate_wave(&self) -> f32 {
-        let freq = self.midi_note_to_freq(self.note);
-        (self.time * freq * 2.0 * PI).sin()
+    // Hosts read the latency from the plugin's `AEffect` rather than asking for it, so a
+    // new figure is written there before asking the host to look again.
+    fn report_latency(&self) {
+        let effect = self.host.raw_effect();
+        let Some(callback) = self.host.raw_callback() else {
+            return;
+        };
+        if effect.is_null() {
+            return;
+        }
+        // The host keeps the plugin's `AEffect` alive for as long as it runs the plugin.
+        unsafe { (*effect).initialDelay = self.latency() };
+        callback(effect, OpCode::IOChanged.into(), 0, 0, std::ptr::null_mut(), 0.0);
     }
 
-    fn midi_note_to_freq(&self, note: u8) -> f32 {
-        const A4_FREQ: f32 = 440.0;
-        const A4_NOTE: i8 = 69;
-        ((note as i8 - A4_NOTE) as f32 / 12.0).exp2() * A4_FREQ
+    fn sysex_message(&mut self, payload: &[u8]) {
+        match sysex::parse(payload) {
+            Some(SysEx::DumpRequest) => self.dump_requested = true,
+            Some(SysEx::Dump(encoded)) => {
+                // Dumps from a build with fewer parameters leave the rest as they are.
+                self.params.restore(sysex::values(encoded));
+                self.host.update_display();
+            }
+            None => {}
+        }
     }
 
-    fn apply_envelope(&self) -> f32 {
-        let attack = self.params.attack.get();
-        let decay = self.params.decay.get();
-        let sustain = self.params.sustain.get();
-        let release = self.params.release.get();
+    fn send_dump(&mut self) {
+        let params = &self.params;
+        sysex::encode_dump((0..PARAMETERS).map(|i| params.get_parameter(i)), &mut self.sysex_out);
+        let dump = SysExEvent {
+            payload: &self.sysex_out,
+            delta_frames: 0,
+        };
+        self.send_buffer.send_events([dump], &mut self.host);
+        self.dump_requested = false;
+    }
 
-        if self.note_on {
-            if self.time < attack {
-                self.time / attack
-            } else if self.time < attack + decay {
-                1.0 - (1.0 - sustain) * (self.time - attack) / decay
+    fn midi_message(&mut self, data: [u8; 3]) {
+        let Some((channel, message)) = self.midi.parse(data) else {
+            return;
+        };
+        if self.params.mpe_enabled() {
+            if channel == 0 {
+                self.master_message(message);
             } else {
-                sustain
+                self.member_message(channel, message);
+            }
+        } else if self.params.input_channel().is_none_or(|input| input == channel) {
+            self.master_message(message);
+        }
+    }
+
+    // The input channel outside MPE, and the zone's master channel within it.
+    fn master_message(&mut self, message: Message) {
+        match message {
+            Message::NoteOff { note } => self.key_off(0, note),
+            Message::NoteOn { note, velocity } => self.key_on(0, note, velocity),
+            Message::PolyPressure { note, pressure } => {
+                self.voices.poly_pressure(0, note, pressure as f32 / 127.0)
+            }
+            Message::ControlChange { controller, value } => self.control_change(controller, value),
+            Message::ProgramChange { program } => self.program_change(program),
+            Message::ChannelPressure { pressure } => self.aftertouch = pressure as f32 / 127.0,
+            Message::PitchBend { bend } => self.pitch_bend = bend,
+        }
+    }
+
+    // An MPE member channel: its bend, pressure and timbre belong to the notes played on
+    // it alone.
+    fn member_message(&mut self, channel: u8, message: Message) {
+        match message {
+            Message::NoteOff { note } => self.key_off(channel, note),
+            Message::NoteOn { note, velocity } => self.key_on(channel, note, velocity),
+            Message::PolyPressure { note, pressure } => {
+                self.voices.poly_pressure(channel, note, pressure as f32 / 127.0)
+            }
+            Message::ControlChange { controller: 74, value } => {
+                self.voices.channel_timbre(channel, value as f32 / 127.0)
+            }
+            Message::ChannelPressure { pressure } => {
+                self.voices.channel_pressure(channel, pressure as f32 / 127.0)
+            }
+            Message::PitchBend { bend } => self.voices.channel_bend(channel, bend),
+            _ => (),
+        }
+    }
+
+    // Programs past the last preset are ignored, so a foot controller set to a higher
+    // bank can't leave the synth silent.
+    fn program_change(&mut self, program: u8) {
+        let program = program as i32;
+        if program < PRESETS && program != self.params.get_preset_num() {
+            self.params.change_preset(program);
+            self.host.update_display();
+        }
+    }
+
+    // A key plays its chord, if chord mode is on, and the chord goes on to `pattern_on`.
+    // While learning, keys play as they are.
+    fn key_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        let learning = self.params.chord_learning();
+        if learning {
+            if let Some(shape) = self.chords.learn(note) {
+                self.params.learned_chord.store(shape.0, Ordering::Relaxed);
             }
+        }
+        let shape = if learning {
+            Shape::ROOT
         } else {
-            if self.time < release {
-                sustain * (1.0 - self.time / release)
-            } else {
-                0.0
+            self.params.chord_shape()
+        };
+        self.chords.press(channel, note, shape);
+        for note in shape.notes(note) {
+            self.strum_on(channel, note, velocity);
+        }
+    }
+
+    fn key_off(&mut self, channel: u8, note: u8) {
+        self.chords.unlearn(note);
+        for note in self.chords.release(channel, note).notes(note) {
+            if !self.strum.off(channel, note) {
+                self.pattern_off(channel, note);
             }
         }
     }
+
+    // Notes starting together are spread out by the strum time, and humanize nudges
+    // each one a little later and louder or softer.
+    fn strum_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        let humanize = self.params.humanize.get();
+        let gap = (self.params.strum_seconds() * self.sample_rate) as u64;
+        let jitter = (self.rng.unit() * humanize * HUMANIZE_TIME * self.sample_rate) as u64;
+        let nudge = self.rng.bipolar() * humanize * HUMANIZE_VELOCITY;
+        let velocity = (velocity as f32 + nudge).round().clamp(1.0, 127.0) as u8;
+        if !self.strum.on(channel, note, velocity, gap, jitter) {
+            self.pattern_on(channel, note, velocity);
+        }
+    }
+
+    // Notes go through a pattern when one is on. Either way, what comes out is played by
+    // `note_on` and `note_off`.
+    fn pattern_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        match self.pattern {
+            Pattern::Off => self.note_on(channel, note, velocity),
+            Pattern::Arpeggiator => self.arpeggiator.press(channel, note, velocity),
+            Pattern::Sequencer => self.note_sequencer.press(channel, note),
+            Pattern::Euclidean => self.euclidean.press(channel, note, velocity),
+        }
+    }
+
+    fn pattern_off(&mut self, channel: u8, note: u8) {
+        match self.pattern {
+            Pattern::Off => self.note_off(channel, note),
+            Pattern::Arpeggiator => self.arpeggiator.release(channel, note),
+            Pattern::Sequencer => self.note_sequencer.release(channel, note),
+            Pattern::Euclidean => self.euclidean.release(channel, note),
+        }
+    }
+
+    // Everything that runs in time starts over, so each pass of a loop, and each bounce,
+    // plays the same way.
+    fn restart_clocks(&mut self) {
+        self.lfo = Lfo::default();
+        self.sample_hold = SampleAndHold::default();
+        self.sequencer = StepSequencer::default();
+        self.chaos = Chaos::default();
+        self.arpeggiator.restart();
+        self.note_sequencer.restart();
+        self.euclidean.restart();
+    }
+
+    fn play_step(&mut self, step: ArpStep) {
+        if let Some(note) = step.off {
+            self.note_off(note.channel, note.note);
+        }
+        if let Some(note) = step.on {
+            self.note_on(note.channel, note.note, note.velocity);
+        }
+    }
+
+    fn play_hit(&mut self, hit: Hit) {
+        for note in hit.off.iter().flat_map(|chord| chord.notes()) {
+            self.note_off(note.channel, note.note);
+        }
+        for note in hit.on.iter().flat_map(|chord| chord.notes()) {
+            self.note_on(note.channel, note.note, note.velocity);
+        }
+    }
+
+    fn note_on(&mut self, channel: u8, note: u8, velocity: u8) {
+        if self.note_output.renders() {
+            self.voices.note_on(&self.params, channel, note, velocity);
+        }
+        if self.note_output.sends() {
+            self.send_midi([0x90 | channel, note, velocity]);
+        }
+    }
+
+    fn note_off(&mut self, channel: u8, note: u8) {
+        if self.note_output.renders() {
+            self.voices.note_off(&self.params, channel, note);
+        }
+        if self.note_output.sends() {
+            self.send_midi([0x80 | channel, note, 0]);
+        }
+    }
+
+    // Whichever side stops getting notes is silenced, or its notes would hang.
+    fn set_note_output(&mut self, output: NoteOutput) {
+        if self.note_output.renders() && !output.renders() {
+            self.voices.all_notes_off();
+        }
+        if self.note_output.sends() && !output.sends() {
+            self.send_all_notes_off();
+        }
+        self.note_output = output;
+    }
+
+    // Keys held across the switch would get their note-off somewhere that never saw the
+    // note-on, so both sides start clean.
+    fn set_pattern(&mut self, pattern: Pattern) {
+        if pattern == self.pattern {
+            return;
+        }
+        if self.pattern == Pattern::Off {
+            self.voices.all_notes_off();
+            if self.note_output.sends() {
+                self.send_all_notes_off();
+            }
+        } else {
+            self.stop_patterns();
+        }
+        self.pattern = pattern;
+    }
+
+    fn stop_patterns(&mut self) {
+        let sounding = [self.arpeggiator.stop(), self.note_sequencer.stop()];
+        for note in sounding.into_iter().flatten() {
+            self.note_off(note.channel, note.note);
+        }
+        if let Some(chord) = self.euclidean.stop() {
+            self.play_hit(Hit {
+                off: Some(chord),
+                on: None,
+            });
+        }
+    }
+
+    fn send_all_notes_off(&mut self) {
+        for channel in 0..16 {
+            self.send_midi([0xB0 | channel, 123, 0]);
+        }
+    }
+
+    // Queued for the end of the block. Past `MAX_PENDING_EVENTS` it's dropped rather than
+    // allocating.
+    fn send_midi(&mut self, data: [u8; 3]) {
+        if self.midi_out.len() < MAX_PENDING_EVENTS {
+            self.midi_out.push(MidiEvent {
+                data,
+                delta_frames: self.frame,
+                live: true,
+                note_length: None,
+                note_offset: None,
+                detune: 0,
+                note_off_velocity: 0,
+            });
+        }
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        if (controller as usize) < LEARNABLE_CCS {
+            if self.learn(controller) {
+                return;
+            }
+            if let Some(index) = self.params.cc_binding(controller) {
+                let value = value as f32 / 127.0;
+                self.params.set_parameter(index, value);
+                self.host.automate(index, value);
+                return;
+            }
+        }
+        match controller {
+            1 => self.mod_wheel = value as f32 / 127.0,
+            // Data entry for RPN 0, pitch bend sensitivity, in whole semitones.
+            6 if self.rpn == PITCH_BEND_SENSITIVITY => {
+                let range = bend_range_to_param(value.clamp(1, 24));
+                self.params.bend_range.set(range);
+                self.host.automate(BEND_RANGE, range);
+            }
+            64 => self.voices.set_sustain(value >= 64),
+            66 => self.voices.set_sostenuto(value >= 64),
+            100 => self.rpn.1 = value,
+            101 => self.rpn.0 = value,
+            120 => {
+                self.chords.clear();
+                self.strum.clear();
+                self.stop_patterns();
+                self.voices.all_sound_off();
+            }
+            123 => {
+                self.chords.clear();
+                self.strum.clear();
+                self.stop_patterns();
+                self.voices.all_notes_off();
+            }
+            _ => (),
+        }
+    }
+
+    // Takes `controller` for MIDI Learn if it is armed, then disarms it. Returns whether
+    // the CC was used up.
+    fn learn(&mut self, controller: u8) -> bool {
+        match self.params.learn_target() {
+            LearnTarget::Off => return false,
+            LearnTarget::Forget => self.params.forget_cc(controller),
+            LearnTarget::Parameter(index) => self.params.bind_cc(controller, index),
+        }
+        self.params.midi_learn.set(0.0);
+        self.host.automate(MIDI_LEARN, 0.0);
+        true
+    }
+}
+
+impl RustSynthParameters {
+    // The factory presets fill the first slots and the user's folder as many of the rest
+    // as it has presets for. The first of them is what plays.
+    fn load_bank_presets(&self) {
+        for (program, (name, values)) in self.programs.iter().zip(program::factory()) {
+            *program.name.lock().unwrap() = name.clone();
+            program.store(values.iter().copied());
+        }
+        self.load_user_presets(None);
+        self.restore(self.programs.current().values());
+    }
+
+    // Saves the current program to the user's folder under `name`, or its own name if
+    // that's empty, names the program to match and reads the folder again so the list
+    // shows it. An existing file is only replaced when this slot holds the user preset of
+    // that name, so saving from another slot can't overwrite one by accident.
+    fn save_preset(&self, name: &str) -> bool {
+        let current = self.programs.current();
+        let own_name = current.name.lock().unwrap().clone();
+        let name = if name.is_empty() { own_name.clone() } else { name.to_string() };
+        let user_slot = self.programs.current_index() >= program::factory().len() as i32;
+        let replace = user_slot && own_name == name;
+        let values: Vec<f32> = self.program_values().collect();
+        if program::save_user_preset(&name, &values, replace).is_err() {
+            return false;
+        }
+        *current.name.lock().unwrap() = name.clone();
+        self.load_user_presets(Some(&name));
+        true
+    }
+
+    // The live values as a program keeps them. MIDI Learn is an action rather than part of
+    // a sound, so it's always kept off.
+    fn program_values(&self) -> impl Iterator<Item = f32> + '_ {
+        (0..PARAMETERS).map(|index| match index {
+            MIDI_LEARN => 0.0,
+            index => self.get_parameter(index),
+        })
+    }
+
+    // Sets the live values from a program's, in index order. MIDI Learn is left as it is,
+    // so restoring a sound never arms it.
+    fn restore(&self, values: impl Iterator<Item = f32>) {
+        for (index, value) in (0..PARAMETERS).zip(values) {
+            if index != MIDI_LEARN {
+                self.set_parameter(index, value);
+            }
+        }
+    }
+
+    // Fills the slots after the factory presets from the user's folder, in file name
+    // order, and resets any left over. Reads the disk, so it's never for the audio thread.
+    // If a user slot is playing, it follows the preset named `saved` to wherever that now
+    // sits; its values are the live ones, so nothing is heard to change.
+    fn load_user_presets(&self, saved: Option<&str>) {
+        let first = program::factory().len();
+        let mut user = program::user_presets().into_iter();
+        for program in self.programs.iter().skip(first) {
+            let (name, values) = user.next().unwrap_or_else(program::init);
+            *program.name.lock().unwrap() = name;
+            program.store(values.into_iter());
+        }
+        let Some(saved) = saved.filter(|_| self.programs.current_index() >= first as i32) else {
+            return;
+        };
+        let slot = (0..).zip(self.programs.iter()).skip(first).find_map(|(index, program)| {
+            (*program.name.lock().unwrap() == saved).then_some(index)
+        });
+        if let Some(slot) = slot {
+            self.programs.set_current(slot);
+        }
+    }
+
+    fn cc_binding(&self, controller: u8) -> Option<i32> {
+        match self.cc_bindings[controller as usize].load(Ordering::Relaxed) {
+            UNBOUND => None,
+            index => Some(index),
+        }
+    }
+
+    // A parameter follows one CC at a time, so binding it drops any older binding.
+    fn bind_cc(&self, controller: u8, index: i32) {
+        for binding in &self.cc_bindings {
+            if binding.load(Ordering::Relaxed) == index {
+                binding.store(UNBOUND, Ordering::Relaxed);
+            }
+        }
+        self.cc_bindings[controller as usize].store(index, Ordering::Relaxed);
+    }
+
+    fn forget_cc(&self, controller: u8) {
+        self.cc_bindings[controller as usize].store(UNBOUND, Ordering::Relaxed);
+    }
+
+    fn chord_shape(&self) -> Shape {
+        self.chord_mode()
+            .shape()
+            .unwrap_or_else(|| Shape(self.learned_chord.load(Ordering::Relaxed)))
+    }
+}
+
+impl Patch {
+    fn get(&self, index: i32) -> f32 {
+        match descriptor(index).and_then(|descriptor| descriptor.value) {
+            Some(value) => value(self, index).get(),
+            None => 0.0,
+        }
+    }
+
+    fn set(&self, index: i32, value: f32) {
+        if let Some(set) = descriptor(index).and_then(|descriptor| descriptor.value) {
+            set(self, index).set(value);
+        }
+    }
+
+    // The value of the parameter at the given index in its unit, through its range.
+    fn plain(&self, index: i32) -> f32 {
+        descriptor::plain(index, self.get(index))
+    }
+
+    fn learn_target(&self) -> LearnTarget {
+        // Off, Forget, then every parameter but this one.
+        let choices = PARAMETERS + 1;
+        match ((self.midi_learn.get() * choices as f32) as i32).min(choices - 1) {
+            0 => LearnTarget::Off,
+            1 => LearnTarget::Forget,
+            choice if choice - 2 < MIDI_LEARN => LearnTarget::Parameter(choice - 2),
+            choice => LearnTarget::Parameter(choice - 1),
+        }
+    }
+
+    // `None` at the very bottom of the knob, which is silent.
+    fn volume_db(&self) -> Option<f32> {
+        let value = self.volume.get();
+        (value > 0.0).then_some(VOLUME.plain(value))
+    }
+
+    fn volume_gain(&self) -> f32 {
+        self.volume_db().map_or(0.0, |db| 10.0f32.powf(db / 20.0))
+    }
+
+    fn amp_envelope(&self) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: self.delay.get(),
+            attack: segment_seconds(self.attack.get()),
+            hold: self.hold.get(),
+            decay: segment_seconds(self.decay.get()),
+            sustain: self.sustain.get(),
+            release: segment_seconds(self.release.get()),
+            curves: self.envelope_curves(),
+            loops: 0,
+        }
+    }
+
+    fn filter_envelope(&self) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: self.filter_delay.get(),
+            attack: segment_seconds(self.filter_attack.get()),
+            hold: self.filter_hold.get(),
+            decay: segment_seconds(self.filter_decay.get()),
+            sustain: self.filter_sustain.get(),
+            release: segment_seconds(self.filter_release.get()),
+            curves: self.envelope_curves(),
+            loops: self.filter_env_loops(),
+        }
+    }
+
+    fn mod_envelope(&self) -> EnvelopeSettings {
+        EnvelopeSettings {
+            delay: 0.0,
+            attack: segment_seconds(self.mod_attack.get()),
+            hold: 0.0,
+            decay: segment_seconds(self.mod_decay.get()),
+            sustain: self.mod_sustain.get(),
+            release: segment_seconds(self.mod_release.get()),
+            curves: self.envelope_curves(),
+            loops: 0,
+        }
+    }
+
+    // Bipolar, -1 to 1.
+    fn mod_env_depth(&self) -> f32 {
+        self.plain(MOD_ENV_AMOUNT)
+    }
+
+    fn mod_env_destination(&self) -> Destination {
+        Destination::from_param(self.mod_env_destination.get())
+    }
+
+    // One of `DIVISIONS` at the host tempo when synced, otherwise the free rate.
+    fn lfo_rate_hz(&self, transport: &Transport) -> f32 {
+        if self.lfo_synced() {
+            transport.rate_hz(self.lfo_division().1)
+        } else {
+            self.lfo_free_rate_hz()
+        }
+    }
+
+    // 0.05 Hz to 50 Hz, evenly spaced in pitch.
+    fn lfo_free_rate_hz(&self) -> f32 {
+        self.plain(LFO_RATE)
+    }
+
+    // Same range as the free-running LFO.
+    fn sample_hold_rate_hz(&self) -> f32 {
+        self.plain(SAMPLE_HOLD_RATE)
+    }
+
+    // Bipolar, -1 to 1.
+    fn sample_hold_depth(&self) -> f32 {
+        self.plain(SAMPLE_HOLD_AMOUNT)
+    }
+
+    fn sample_hold_destination(&self) -> Destination {
+        Destination::from_param(self.sample_hold_destination.get())
+    }
+
+    // Onset delay and fade-in both run up to four seconds.
+    fn lfo_delay_seconds(&self) -> f32 {
+        self.plain(LFO_DELAY)
+    }
+
+    fn lfo_fade_seconds(&self) -> f32 {
+        self.plain(LFO_FADE_IN)
+    }
+
+    fn lfo_synced(&self) -> bool {
+        self.lfo_sync.get() >= 0.5
+    }
+
+    fn lfo_division(&self) -> (&'static str, f32) {
+        division_from_param(self.lfo_rate.get())
+    }
+
+    // The sequencer always follows the host tempo, one step per division.
+    fn sequencer_rate_hz(&self, transport: &Transport) -> f32 {
+        transport.rate_hz(self.sequencer_division().1)
+    }
+
+    fn sequencer_division(&self) -> (&'static str, f32) {
+        division_from_param(self.sequencer_rate.get())
+    }
+
+    // Bipolar, -1 to 1.
+    fn mod_wheel_depth(&self) -> f32 {
+        self.plain(WHEEL_AMOUNT)
+    }
+
+    fn mod_wheel_destination(&self) -> Destination {
+        Destination::from_param(self.mod_wheel_destination.get())
+    }
+
+    fn voice_mode(&self) -> VoiceMode {
+        VoiceMode::from_param(self.voice_mode.get())
+    }
+
+    // Polyphony, from 1 to `MAX_VOICES`.
+    fn max_voices(&self) -> usize {
+        1 + (self.max_voices.get() * (MAX_VOICES - 1) as f32).round() as usize
+    }
+
+    fn note_output(&self) -> NoteOutput {
+        NoteOutput::from_param(self.note_output.get())
+    }
+
+    // With more than one on, the arpeggiator wins, then the note sequencer.
+    fn pattern(&self) -> Pattern {
+        if self.arp_enabled() {
+            Pattern::Arpeggiator
+        } else if self.note_seq_enabled() {
+            Pattern::Sequencer
+        } else if self.euclid_enabled() {
+            Pattern::Euclidean
+        } else {
+            Pattern::Off
+        }
+    }
+
+    fn arp_enabled(&self) -> bool {
+        self.arp.get() >= 0.5
+    }
+
+    fn arp_mode(&self) -> ArpMode {
+        ArpMode::from_param(self.arp_mode.get())
+    }
+
+    // Octaves the pattern spans, from 1 to `MAX_OCTAVES`.
+    fn arp_octaves(&self) -> usize {
+        1 + (self.arp_octaves.get() * (MAX_OCTAVES - 1) as f32).round() as usize
+    }
+
+    fn arp_rate_hz(&self, transport: &Transport) -> f32 {
+        transport.rate_hz(self.arp_division().1)
+    }
+
+    fn arp_division(&self) -> (&'static str, f32) {
+        division_from_param(self.arp_rate.get())
+    }
+
+    // Fraction of each step a note is held for: from a short blip to fully legato.
+    fn arp_gate(&self) -> f32 {
+        self.arp_gate.get().max(0.01)
+    }
+
+    fn note_seq_enabled(&self) -> bool {
+        self.note_seq.get() >= 0.5
+    }
+
+    fn note_seq_rate_hz(&self, transport: &Transport) -> f32 {
+        transport.rate_hz(self.note_seq_division().1)
+    }
+
+    fn note_seq_division(&self) -> (&'static str, f32) {
+        division_from_param(self.note_seq_rate.get())
+    }
+
+    fn note_steps(&self) -> [NoteStep; NOTE_STEPS] {
+        std::array::from_fn(|step| self.note_steps[step].step())
+    }
+
+    fn euclid_enabled(&self) -> bool {
+        self.euclid.get() >= 0.5
+    }
+
+    fn euclid_rate_hz(&self, transport: &Transport) -> f32 {
+        transport.rate_hz(self.euclid_division().1)
+    }
+
+    fn euclid_division(&self) -> (&'static str, f32) {
+        division_from_param(self.euclid_rate.get())
+    }
+
+    fn euclid_rhythm(&self) -> Rhythm {
+        let steps = 1 + (self.euclid_steps.get() * (MAX_STEPS - 1) as f32).round() as usize;
+        let pulses = (self.euclid_pulses.get() * MAX_STEPS as f32).round() as usize;
+        let rotation = (self.euclid_rotation.get() * (MAX_STEPS - 1) as f32).round() as usize;
+        Rhythm {
+            steps,
+            pulses: pulses.min(steps),
+            rotation: rotation % steps,
+        }
+    }
+
+    fn euclid_gate(&self) -> f32 {
+        self.euclid_gate.get().max(0.01)
+    }
+
+    fn note_step_parameter(&self, index: i32) -> &AtomicFloat {
+        let (step, field) = note_step_field(index);
+        let step = &self.note_steps[step];
+        match field {
+            0 => &step.pitch,
+            1 => &step.gate,
+            _ => &step.velocity,
+        }
+    }
+
+    fn chord_mode(&self) -> ChordMode {
+        ChordMode::from_param(self.chord_mode.get())
+    }
+
+    fn chord_learning(&self) -> bool {
+        self.chord_learn.get() >= 0.5
+    }
+
+    // Up to 50 ms between the notes of a chord.
+    fn strum_seconds(&self) -> f32 {
+        self.plain(STRUM) / 1000.0
+    }
+
+    fn transport_reset_enabled(&self) -> bool {
+        self.transport_reset.get() >= 0.5
+    }
+
+    fn quality(&self) -> Quality {
+        Quality::from_param(self.quality.get())
+    }
+
+    fn glide_enabled(&self) -> bool {
+        self.glide.get() >= 0.5
+    }
+
+    // Up to two seconds.
+    fn glide_seconds(&self) -> f32 {
+        self.plain(GLIDE_TIME)
+    }
+
+    // `None` listens on every channel.
+    fn input_channel(&self) -> Option<u8> {
+        match (self.midi_channel.get() * 16.0).round() as u8 {
+            0 => None,
+            channel => Some(channel - 1),
+        }
+    }
+
+    // Lower zone only: channel 1 is the master channel and 2-16 carry one note each.
+    fn mpe_enabled(&self) -> bool {
+        self.mpe.get() >= 0.5
+    }
+
+    // Whole semitones, from 1 to 24.
+    fn bend_range_semitones(&self) -> u8 {
+        1 + (self.bend_range.get() * 23.0).round() as u8
+    }
+
+    // Same range as the free-running LFO.
+    fn chaos_rate_hz(&self) -> f32 {
+        self.plain(CHAOS_RATE)
+    }
+
+    // Step values, bipolar from -1 to 1.
+    fn sequencer_values(&self) -> [f32; SEQUENCER_STEPS] {
+        self.sequencer_steps.each_ref().map(|step| step.get() * 2.0 - 1.0)
+    }
+
+    // Vibrato depth either side of the note, up to an octave. `modulation` is whatever
+    // reaches the LFO Depth destination.
+    fn lfo_depth_semitones(&self, modulation: f32) -> f32 {
+        descriptor::plain(LFO_DEPTH, (self.lfo_depth.get() + modulation).clamp(0.0, 1.0))
+    }
+
+    // Off, one to eight repeats, or looping for as long as the key is held.
+    fn filter_env_loops(&self) -> u32 {
+        match (self.filter_loop.get() * 9.0).round() as u32 {
+            9 => LOOP_FOREVER,
+            loops => loops,
+        }
+    }
+
+    // Shared by every envelope in the synth.
+    fn envelope_curves(&self) -> EnvelopeCurves {
+        EnvelopeCurves {
+            attack: self.attack_curve.get(),
+            decay: self.decay_curve.get(),
+            release: self.release_curve.get(),
+        }
+    }
+
+    fn osc2_coarse_semitones(&self) -> i32 {
+        (self.osc2_coarse.get() * 48.0 - 24.0).round() as i32
+    }
+
+    fn osc2_fine_cents(&self) -> f32 {
+        self.plain(OSC2_FINE)
+    }
+
+    // Total osc2 offset from the played note, in semitones.
+    fn osc2_detune(&self) -> f32 {
+        self.osc2_coarse_semitones() as f32 + self.osc2_fine_cents() / 100.0
+    }
+
+    fn sync_enabled(&self) -> bool {
+        self.sync.get() >= 0.5
+    }
+
+    // How far osc1 is tuned above the note while slaved to osc2.
+    fn sync_tune_semitones(&self) -> f32 {
+        self.plain(SYNC_TUNE)
+    }
+
+    fn pulse_width_fraction(&self) -> f32 {
+        self.plain(PULSE_WIDTH)
+    }
+
+    fn pwm_rate_hz(&self) -> f32 {
+        self.plain(PWM_RATE)
+    }
+
+    // Modulation index, in radians of osc1 phase deviation.
+    fn fm_index(&self) -> f32 {
+        self.fm_amount.get() * 10.0
+    }
+
+    // Osc2 frequency as a multiple of the note, snapped to musically useful ratios.
+    fn fm_ratio(&self) -> f32 {
+        ratio_from_param(self.fm_ratio.get())
+    }
+
+    fn fm_algorithm(&self) -> &'static fm::Algorithm {
+        let index = (self.fm_algorithm.get() * ALGORITHMS.len() as f32) as usize;
+        &ALGORITHMS[index.min(ALGORITHMS.len() - 1)]
+    }
+
+    fn fm_operator_ratio(&self, op: usize) -> f32 {
+        ratio_from_param(self.fm_operators[op].ratio.get())
+    }
+
+    fn fm_operator_parameter(&self, index: i32) -> &AtomicFloat {
+        let (op, field) = fm_operator_field(index);
+        let op = &self.fm_operators[op];
+        match field {
+            0 => &op.ratio,
+            1 => &op.level,
+            2 => &op.attack,
+            3 => &op.decay,
+            4 => &op.sustain,
+            _ => &op.release,
+        }
+    }
+
+    fn mod_slot_parameter(&self, index: i32) -> &AtomicFloat {
+        let (slot, field) = mod_slot_field(index);
+        let slot = &self.mod_slots[slot];
+        match field {
+            0 => &slot.source,
+            1 => &slot.destination,
+            _ => &slot.amount,
+        }
+    }
+
+    fn macro_parameter(&self, index: i32) -> &AtomicFloat {
+        let (knob, field) = macro_field(index);
+        let knob = &self.macros[knob];
+        match field {
+            MacroField::Value => &knob.value,
+            MacroField::Destination(target) => &knob.targets[target].destination,
+            MacroField::Amount(target) => &knob.targets[target].amount,
+        }
+    }
+
+    fn harmonic_count(&self) -> usize {
+        1 + (self.harmonics.get() * (MAX_HARMONICS - 1) as f32).round() as usize
+    }
+
+    fn additive_amplitudes(&self) -> [f32; MAX_HARMONICS] {
+        let custom = self.harmonic_levels.each_ref().map(|level| level.get());
+        let profile = HarmonicProfile::from_param(self.harmonic_profile.get());
+        profile.amplitudes(self.harmonic_count(), &custom)
+    }
+
+    // Time for a plucked note to fall by 60 dB, from 0.1 s to 10 s.
+    fn pluck_decay_seconds(&self) -> f32 {
+        self.plain(PLUCK_DECAY)
+    }
+
+    fn unison_voices(&self) -> usize {
+        1 + (self.unison.get() * (MAX_UNISON - 1) as f32).round() as usize
+    }
+
+    // Distance of the outermost unison copies from the note, in cents.
+    fn unison_detune_cents(&self) -> f32 {
+        self.plain(UNISON_DETUNE)
+    }
+
+    // Grain length, from 10 ms to 500 ms.
+    fn grain_size_seconds(&self) -> f32 {
+        self.plain(GRAIN_SIZE) / 1000.0
+    }
+
+    // Grains started per second, from 1 to 100.
+    fn grain_density_hz(&self) -> f32 {
+        self.plain(GRAIN_DENSITY)
+    }
+
+    // Maximum random pitch offset applied to each grain.
+    fn grain_jitter_semitones(&self) -> f32 {
+        self.plain(GRAIN_JITTER)
+    }
+
+    fn drum_tune_semitones(&self) -> f32 {
+        self.plain(DRUM_TUNE)
+    }
+
+    // Multiplier on every drum decay time, from 0.25x to 4x.
+    fn drum_decay_scale(&self) -> f32 {
+        self.plain(DRUM_DECAY)
+    }
+
+    fn cutoff_hz(&self) -> f32 {
+        cutoff_from_param(self.cutoff.get())
+    }
+
+    // How far the filter envelope sweeps the cutoff at full level, up or down.
+    fn filter_env_octaves(&self) -> f32 {
+        self.plain(FILTER_ENV_AMOUNT)
+    }
+
+    // Octaves of cutoff shift per octave of pitch, from 0 to 2.
+    fn key_track_amount(&self) -> f32 {
+        self.plain(KEY_TRACK)
+    }
+
+    // Comb pitch relative to the played note.
+    fn comb_tune_semitones(&self) -> f32 {
+        self.plain(COMB_TUNE)
+    }
+
+    fn drawbar_levels(&self) -> [f32; DRAWBARS] {
+        self.drawbars.each_ref().map(|drawbar| drawbar.get())
+    }
+
+    // How many octaves the sub-oscillator sits below osc1.
+    fn sub_octaves(&self) -> i32 {
+        if self.sub_octave.get() < 0.5 {
+            1
+        } else {
+            2
+        }
+    }
 }
 
 impl PluginParameters for RustSynthParameters {
+    // The live values go back into the program being left, so switching away and back
+    // keeps its edits.
+    fn change_preset(&self, preset: i32) {
+        let Some(program) = self.programs.get(preset) else {
+            return;
+        };
+        self.programs.current().store(self.program_values());
+        self.restore(program.values());
+        self.programs.set_current(preset);
+    }
+
+    fn get_preset_num(&self) -> i32 {
+        self.programs.current_index()
+    }
+
+    // Hosts rename programs while restoring projects too, so this never saves.
+    fn set_preset_name(&self, name: String) {
+        *self.programs.current().name.lock().unwrap() = name;
+    }
+
+    fn get_preset_name(&self, preset: i32) -> String {
+        self.programs.get(preset).map_or(String::new(), |p| p.name.lock().unwrap().clone())
+    }
+
+    fn get_preset_data(&self) -> Vec<u8> {
+        state::save_preset(self).into_bytes()
+    }
+
+    fn get_bank_data(&self) -> Vec<u8> {
+        state::save_bank(self).into_bytes()
+    }
+
+    fn load_preset_data(&self, data: &[u8]) {
+        state::load_preset(self, data);
+    }
+
+    fn load_bank_data(&self, data: &[u8]) {
+        state::load_bank(self, data);
+    }
+
     fn get_parameter(&self, index: i32) -> f32 {
-        match index {
-            0 => self.volume.get(),
-            1 => self.attack.get(),
-            2 => self.decay.get(),
-            3 => self.sustain.get(),
-            4 => self.release.get(),
-            _ => 0.0,
-        }
+        self.patch.get(index)
     }
 
     fn set_parameter(&self, index: i32, value: f32) {
-        match index {
-            0 => self.volume.set(value),
-            1 => self.attack.set(value),
-            2 => self.decay.set(value),
-            3 => self.sustain.set(value),
-            4 => self.release.set(value),
-            _ => (),
-        }
+        self.patch.set(index, value);
     }
 
     fn get_parameter_name(&self, index: i32) -> String {
-        match index {
-            0 => "Volume".to_string(),
-            1 => "Attack".to_string(),
-            2 => "Decay".to_string(),
-            3 => "Sustain".to_string(),
-            4 => "Release".to_string(),
-            _ => "".to_string(),
-        }
+        descriptor(index).map_or(String::new(), |d| d.full_name())
     }
 
     fn get_parameter_label(&self, index: i32) -> String {
+        match descriptor(index) {
+            // A synced LFO rate reads as a note division.
+            Some(_) if index == LFO_RATE && self.lfo_synced() => String::new(),
+            Some(d) => d.unit.label().to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn get_parameter_text(&self, index: i32) -> String {
+        descriptor(index).map_or(String::new(), |d| (d.text)(&self.patch, index))
+    }
+
+    // The sample slot holds a file path rather than a value, so it is set by typing the
+    // path into the host's parameter field. Save Preset likewise takes a name.
+    fn can_be_automated(&self, index: i32) -> bool {
+        descriptor(index).is_some_and(|descriptor| descriptor.automatable)
+    }
+
+    fn string_to_parameter(&self, index: i32, text: String) -> bool {
         match index {
-            0 => "%".to_string(),
-            1 | 2 | 4 => "s".to_string(),
-            3 => "%".to_string(),
-            _ => "".to_string(),
+            SAMPLE => match Sample::load(Path::new(text.trim())) {
+                Ok(sample) => {
+                    *self.sample.lock().unwrap() = Some(Arc::new(sample));
+                    true
+                }
+                Err(_) => false,
+            },
+            SAVE_PRESET => self.save_preset(text.trim()),
+            _ => match descriptor::parse(self, index, &text) {
+                Some(value) => {
+                    self.set_parameter(index, value);
+                    true
+                }
+                None => false,
+            },
         }
     }
 }
 
+// 20 Hz to 20 kHz, evenly spaced in pitch.
+fn cutoff_from_param(value: f32) -> f32 {
+    descriptor::plain(CUTOFF, value)
+}
+
+// Saturator input gain, up to 20x with most of the travel in the gentle range.
+fn drive_from_param(value: f32) -> f32 {
+    20.0 * value * value
+}
+
+fn ratio_from_param(value: f32) -> f32 {
+    let index = (value * FM_RATIOS.len() as f32) as usize;
+    FM_RATIOS[index.min(FM_RATIOS.len() - 1)]
+}
+
+// Centre of the parameter range that selects `ratio`, so it survives float round-trips.
+fn ratio_to_param(ratio: f32) -> f32 {
+    let index = FM_RATIOS.iter().position(|&r| r == ratio).unwrap_or(0);
+    (index as f32 + 0.5) / FM_RATIOS.len() as f32
+}
+
+fn division_from_param(value: f32) -> (&'static str, f32) {
+    let index = (value * DIVISIONS.len() as f32) as usize;
+    DIVISIONS[index.min(DIVISIONS.len() - 1)]
+}
+
+// Centre of the parameter range that selects the division called `name`.
+fn division_to_param(name: &str) -> f32 {
+    let index = DIVISIONS.iter().position(|&(n, _)| n == name).unwrap_or(0);
+    (index as f32 + 0.5) / DIVISIONS.len() as f32
+}
+
+// Operator parameters are laid out as six consecutive slots per operator.
+fn fm_operator_field(index: i32) -> (usize, usize) {
+    let offset = (index - FM_OPERATORS_START) as usize;
+    (offset / 6, offset % 6)
+}
+
+// Mod matrix slots are laid out as source, destination and amount.
+fn mod_slot_field(index: i32) -> (usize, usize) {
+    let offset = (index - MOD_SLOTS_START) as usize;
+    (offset / 3, offset % 3)
+}
+
+// Note sequencer steps are laid out as pitch, gate and velocity.
+fn note_step_field(index: i32) -> (usize, usize) {
+    let offset = (index - NOTE_STEPS_START) as usize;
+    (offset / 3, offset % 3)
+}
+
+// What turns held keys into notes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pattern {
+    Off,
+    Arpeggiator,
+    Sequencer,
+    Euclidean,
+}
+
+// What the next CC does while MIDI Learn is armed: bind itself to a parameter, or drop
+// its binding.
+enum LearnTarget {
+    Off,
+    Forget,
+    Parameter(i32),
+}
+
+enum MacroField {
+    Value,
+    Destination(usize),
+    Amount(usize),
+}
+
+// Each macro takes its knob followed by a destination and amount per target.
+fn macro_field(index: i32) -> (usize, MacroField) {
+    let offset = (index - MACROS_START) as usize;
+    let per_macro = 1 + 2 * MACRO_TARGETS;
+    let field = match offset % per_macro {
+        0 => MacroField::Value,
+        field => {
+            let target = (field - 1) / 2;
+            if field % 2 == 1 {
+                MacroField::Destination(target)
+            } else {
+                MacroField::Amount(target)
+            }
+        }
+    };
+    (offset / per_macro, field)
+}
+
+fn max_voices_to_param(voices: usize) -> f32 {
+    (voices - 1) as f32 / (MAX_VOICES - 1) as f32
+}
+
+fn bend_range_to_param(semitones: u8) -> f32 {
+    (semitones - 1) as f32 / 23.0
+}
+
+fn on_off(enabled: bool) -> String {
+    if enabled { "On" } else { "Off" }.to_string()
+}
+
 plugin_main!(RustSynth);
 
@@ -0,0 +1,101 @@
+// Channel voice messages the synth responds to. Values are the raw 7-bit data, apart
+// from pitch bend, which is scaled to -1 to 1.
+#[derive(Clone, Copy)]
+pub enum Message {
+    NoteOff { note: u8 },
+    NoteOn { note: u8, velocity: u8 },
+    PolyPressure { note: u8, pressure: u8 },
+    ControlChange { controller: u8, value: u8 },
+    ProgramChange { program: u8 },
+    ChannelPressure { pressure: u8 },
+    PitchBend { bend: f32 },
+}
+
+// Where the notes the synth plays go: its own voices, out to the host as MIDI, or
+// both, so it can drive other instruments.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NoteOutput {
+    Audio,
+    Both,
+    Midi,
+}
+
+impl NoteOutput {
+    const ALL: [NoteOutput; 3] = [NoteOutput::Audio, NoteOutput::Both, NoteOutput::Midi];
+
+    pub fn from_param(value: f32) -> NoteOutput {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteOutput::Audio => "Audio",
+            NoteOutput::Both => "Audio + MIDI",
+            NoteOutput::Midi => "MIDI",
+        }
+    }
+
+    pub fn renders(&self) -> bool {
+        *self != NoteOutput::Midi
+    }
+
+    pub fn sends(&self) -> bool {
+        *self != NoteOutput::Audio
+    }
+}
+
+// Turns raw event bytes into messages, remembering the last status byte so a packet
+// that starts with data bytes (running status) still parses.
+#[derive(Default)]
+pub struct Parser {
+    running_status: Option<u8>,
+}
+
+impl Parser {
+    // Returns the message and its channel, from 0 to 15, or `None` for anything
+    // malformed or outside the channel voice messages.
+    pub fn parse(&mut self, bytes: [u8; 3]) -> Option<(u8, Message)> {
+        let (status, data) = if bytes[0] & 0x80 == 0 {
+            (self.running_status?, [bytes[0], bytes[1]])
+        } else {
+            (bytes[0], [bytes[1], bytes[2]])
+        };
+        if status >= 0xF0 {
+            // System common messages cancel running status; real-time ones leave it be.
+            if status < 0xF8 {
+                self.running_status = None;
+            }
+            return None;
+        }
+        self.running_status = Some(status);
+
+        let data_bytes = match status & 0xF0 {
+            0xC0 | 0xD0 => 1,
+            _ => 2,
+        };
+        if data[..data_bytes].iter().any(|byte| byte & 0x80 != 0) {
+            return None;
+        }
+
+        let [first, second] = data;
+        let message = match status & 0xF0 {
+            0x80 => Message::NoteOff { note: first },
+            // Velocity 0 is a note-off by convention.
+            0x90 if second == 0 => Message::NoteOff { note: first },
+            0x90 => Message::NoteOn { note: first, velocity: second },
+            0xA0 => Message::PolyPressure { note: first, pressure: second },
+            0xB0 => Message::ControlChange { controller: first, value: second },
+            0xC0 => Message::ProgramChange { program: first },
+            0xD0 => Message::ChannelPressure { pressure: first },
+            _ => Message::PitchBend { bend: pitch_bend_from_bytes(first, second) },
+        };
+        Some((status & 0x0F, message))
+    }
+}
+
+// 14-bit bend, least significant seven bits first, centred on 8192.
+fn pitch_bend_from_bytes(lsb: u8, msb: u8) -> f32 {
+    let value = ((msb as i32) << 7 | lsb as i32) - 8192;
+    value as f32 / 8192.0
+}
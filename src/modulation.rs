@@ -0,0 +1,298 @@
+use vst::util::AtomicFloat;
+
+pub const MOD_SLOTS: usize = 8;
+pub const MACROS: usize = 4;
+pub const MACRO_TARGETS: usize = 3;
+
+// Everything a modulation source can be routed to. Each destination takes a bipolar
+// amount; at ±1 it moves by the range noted beside it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    None,
+    // ±24 semitones.
+    Pitch,
+    // ±45% of the period.
+    PulseWidth,
+    // The full wavetable.
+    WtPosition,
+    // ±8 octaves.
+    Cutoff,
+    // The full osc1-osc2 crossfade.
+    OscMix,
+    // The full FM index range.
+    FmAmount,
+    // The full vector square.
+    VectorX,
+    VectorY,
+    // Silence to double the level.
+    Amp,
+    // The full LFO Depth range, so up to an octave of vibrato.
+    LfoDepth,
+}
+
+const DESTINATIONS: usize = 11;
+
+impl Destination {
+    const ALL: [Destination; DESTINATIONS] = [
+        Destination::None,
+        Destination::Pitch,
+        Destination::PulseWidth,
+        Destination::WtPosition,
+        Destination::Cutoff,
+        Destination::OscMix,
+        Destination::FmAmount,
+        Destination::VectorX,
+        Destination::VectorY,
+        Destination::Amp,
+        Destination::LfoDepth,
+    ];
+
+    pub fn from_param(value: f32) -> Destination {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    // Centre of the parameter range that selects this destination.
+    pub fn to_param(self) -> f32 {
+        let index = Self::ALL.iter().position(|&d| d == self).unwrap_or(0);
+        (index as f32 + 0.5) / Self::ALL.len() as f32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Destination::None => "None",
+            Destination::Pitch => "Pitch",
+            Destination::PulseWidth => "Pulse Width",
+            Destination::WtPosition => "WT Position",
+            Destination::Cutoff => "Cutoff",
+            Destination::OscMix => "Osc Mix",
+            Destination::FmAmount => "FM Amount",
+            Destination::VectorX => "Vector X",
+            Destination::VectorY => "Vector Y",
+            Destination::Amp => "Amp",
+            Destination::LfoDepth => "LFO Depth",
+        }
+    }
+}
+
+// What a mod matrix slot reads. Envelopes, velocity and the controllers run from 0 to 1;
+// the LFO, sample-and-hold, sequencer, chaos, note number and random value are bipolar.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    None,
+    Lfo,
+    SampleHold,
+    Sequencer,
+    Chaos,
+    AmpEnvelope,
+    FilterEnvelope,
+    ModEnvelope,
+    Velocity,
+    // -1 at C-1, 0 at middle C, about +1 at the top of the keyboard.
+    NoteNumber,
+    ModWheel,
+    Aftertouch,
+    // Key pressure for this voice's note alone.
+    PolyPressure,
+    // MPE CC74 on this voice's channel.
+    Timbre,
+    // Drawn afresh at each note-on and held for the life of the note.
+    Random,
+}
+
+impl Source {
+    const ALL: [Source; 15] = [
+        Source::None,
+        Source::Lfo,
+        Source::SampleHold,
+        Source::Sequencer,
+        Source::Chaos,
+        Source::AmpEnvelope,
+        Source::FilterEnvelope,
+        Source::ModEnvelope,
+        Source::Velocity,
+        Source::NoteNumber,
+        Source::ModWheel,
+        Source::Aftertouch,
+        Source::PolyPressure,
+        Source::Timbre,
+        Source::Random,
+    ];
+
+    pub fn from_param(value: f32) -> Source {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::None => "None",
+            Source::Lfo => "LFO",
+            Source::SampleHold => "S&H",
+            Source::Sequencer => "Sequencer",
+            Source::Chaos => "Chaos",
+            Source::AmpEnvelope => "Amp Env",
+            Source::FilterEnvelope => "Filter Env",
+            Source::ModEnvelope => "Mod Env",
+            Source::Velocity => "Velocity",
+            Source::NoteNumber => "Note",
+            Source::ModWheel => "Mod Wheel",
+            Source::Aftertouch => "Aftertouch",
+            Source::PolyPressure => "Poly AT",
+            Source::Timbre => "Timbre",
+            Source::Random => "Random",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ModSlotParameters {
+    pub source: AtomicFloat,
+    pub destination: AtomicFloat,
+    pub amount: AtomicFloat,
+}
+
+impl ModSlotParameters {
+    pub fn source(&self) -> Source {
+        Source::from_param(self.source.get())
+    }
+
+    pub fn destination(&self) -> Destination {
+        Destination::from_param(self.destination.get())
+    }
+
+    // Bipolar, -1 to 1.
+    pub fn depth(&self) -> f32 {
+        self.amount.get() * 2.0 - 1.0
+    }
+}
+
+#[derive(Default)]
+pub struct MacroTarget {
+    pub destination: AtomicFloat,
+    pub amount: AtomicFloat,
+}
+
+// One knob driving up to `MACRO_TARGETS` destinations. Each target moves by its own
+// bipolar amount as the knob goes from 0 to 1.
+#[derive(Default)]
+pub struct MacroParameters {
+    pub value: AtomicFloat,
+    pub targets: [MacroTarget; MACRO_TARGETS],
+}
+
+impl MacroParameters {
+    pub fn destination(&self, target: usize) -> Destination {
+        Destination::from_param(self.targets[target].destination.get())
+    }
+
+    // Bipolar, -1 to 1.
+    pub fn depth(&self, target: usize) -> f32 {
+        self.targets[target].amount.get() * 2.0 - 1.0
+    }
+}
+
+// Sources shared by every voice, worked out once per control block. The LFO, sample-and-hold,
+// sequencer, chaos and pitch bend run from -1 to 1, the other controllers from 0 to 1.
+#[derive(Clone, Copy)]
+pub struct SharedSources {
+    pub lfo: f32,
+    pub sample_hold: f32,
+    pub sequencer: f32,
+    pub chaos: f32,
+    pub pitch_bend: f32,
+    pub mod_wheel: f32,
+    pub aftertouch: f32,
+}
+
+// Every source's value for one voice this control block.
+#[derive(Clone, Copy)]
+pub struct SourceValues {
+    pub shared: SharedSources,
+    pub amp_envelope: f32,
+    pub filter_envelope: f32,
+    pub mod_envelope: f32,
+    pub velocity: f32,
+    pub note: u8,
+    pub pressure: f32,
+    pub timbre: f32,
+    pub random: f32,
+}
+
+impl SourceValues {
+    pub fn get(&self, source: Source) -> f32 {
+        match source {
+            Source::None => 0.0,
+            Source::Lfo => self.shared.lfo,
+            Source::SampleHold => self.shared.sample_hold,
+            Source::Sequencer => self.shared.sequencer,
+            Source::Chaos => self.shared.chaos,
+            Source::AmpEnvelope => self.amp_envelope,
+            Source::FilterEnvelope => self.filter_envelope,
+            Source::ModEnvelope => self.mod_envelope,
+            Source::Velocity => self.velocity,
+            Source::NoteNumber => (self.note as f32 - 60.0) / 60.0,
+            Source::ModWheel => self.shared.mod_wheel,
+            Source::Aftertouch => self.shared.aftertouch,
+            Source::PolyPressure => self.pressure,
+            Source::Timbre => self.timbre,
+            Source::Random => self.random,
+        }
+    }
+}
+
+// The modulation reaching each destination of one voice this control block. Sources add
+// into it in the voice's control update, and the places that use a destination read the
+// total back.
+#[derive(Clone, Copy, Default)]
+pub struct Modulation {
+    amounts: [f32; DESTINATIONS],
+}
+
+impl Modulation {
+    pub fn clear(&mut self) {
+        self.amounts = [0.0; DESTINATIONS];
+    }
+
+    pub fn add(&mut self, destination: Destination, amount: f32) {
+        if destination != Destination::None {
+            self.amounts[destination as usize] += amount;
+        }
+    }
+
+    pub fn get(&self, destination: Destination) -> f32 {
+        self.amounts[destination as usize]
+    }
+
+    pub fn add_slots(&mut self, slots: &[ModSlotParameters; MOD_SLOTS], sources: &SourceValues) {
+        for slot in slots {
+            let source = slot.source();
+            if source != Source::None {
+                self.add(slot.destination(), sources.get(source) * slot.depth());
+            }
+        }
+    }
+
+    pub fn add_macros(&mut self, macros: &[MacroParameters; MACROS]) {
+        for knob in macros {
+            let value = knob.value.get();
+            for target in 0..MACRO_TARGETS {
+                self.add(knob.destination(target), value * knob.depth(target));
+            }
+        }
+    }
+}
+
+// One-pole low-pass for controller streams, which arrive as 7-bit steps at whatever rate
+// the controller sends them.
+#[derive(Default)]
+pub struct Smoother {
+    value: f32,
+}
+
+impl Smoother {
+    pub fn next(&mut self, target: f32, time: f32, per_sample: f32) -> f32 {
+        self.value += (target - self.value) * (1.0 - (-per_sample / time).exp());
+        self.value
+    }
+}
@@ -0,0 +1,61 @@
+use crate::random::Random;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoiseColor {
+    #[default]
+    White,
+    Pink,
+    Brown,
+}
+
+impl NoiseColor {
+    const ALL: [NoiseColor; 3] = [NoiseColor::White, NoiseColor::Pink, NoiseColor::Brown];
+
+    pub fn from_param(value: f32) -> NoiseColor {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseColor::White => "White",
+            NoiseColor::Pink => "Pink",
+            NoiseColor::Brown => "Brown",
+        }
+    }
+}
+
+// Pink uses Paul Kellet's filter bank, brown a leaky integrator; both are scaled to
+// roughly the same loudness as white.
+#[derive(Clone, Copy, Default)]
+pub struct NoiseGenerator {
+    pink: [f32; 7],
+    brown: f32,
+    rng: Random,
+}
+
+impl NoiseGenerator {
+    pub fn next(&mut self, color: NoiseColor) -> f32 {
+        let white = self.rng.bipolar();
+
+        match color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => {
+                let b = &mut self.pink;
+                b[0] = 0.99886 * b[0] + white * 0.0555179;
+                b[1] = 0.99332 * b[1] + white * 0.0750759;
+                b[2] = 0.96900 * b[2] + white * 0.153852;
+                b[3] = 0.86650 * b[3] + white * 0.3104856;
+                b[4] = 0.55000 * b[4] + white * 0.5329522;
+                b[5] = -0.7616 * b[5] - white * 0.0168980;
+                let pink = b.iter().sum::<f32>() + white * 0.5362;
+                b[6] = white * 0.115926;
+                pink * 0.11
+            }
+            NoiseColor::Brown => {
+                self.brown = (self.brown + 0.02 * white) / 1.02;
+                self.brown * 3.5
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use vst::util::AtomicFloat;
+
+use crate::arpeggiator::{ArpNote, ArpStep};
+
+pub const NOTE_STEPS: usize = 16;
+const MAX_KEYS: usize = 32;
+// Semitones a step can sit above or below the key.
+const STEP_RANGE: i32 = 24;
+
+#[derive(Clone, Copy, Default)]
+pub struct NoteStep {
+    // Semitones from the held key.
+    pub pitch: i32,
+    // Fraction of the step the note is held for; 0 is a rest.
+    pub gate: f32,
+    pub velocity: u8,
+}
+
+#[derive(Default)]
+pub struct NoteStepParameters {
+    pub pitch: AtomicFloat,
+    pub gate: AtomicFloat,
+    pub velocity: AtomicFloat,
+}
+
+impl NoteStepParameters {
+    pub fn step(&self) -> NoteStep {
+        NoteStep {
+            pitch: (self.pitch.get() * (2 * STEP_RANGE) as f32).round() as i32 - STEP_RANGE,
+            gate: self.gate.get(),
+            velocity: 1 + (self.velocity.get() * 126.0).round() as u8,
+        }
+    }
+}
+
+pub fn velocity_to_param(velocity: u8) -> f32 {
+    (velocity - 1) as f32 / 126.0
+}
+
+// Plays a fixed pattern of notes, clocked from the host tempo, transposed by the key
+// being held. Unlike the arpeggiator, one key is enough.
+pub struct NoteSequencer {
+    // Keys down, oldest first; the newest one transposes the pattern.
+    keys: [(u8, u8); MAX_KEYS],
+    len: usize,
+    step: usize,
+    // Progress through the current step; at 1 the next one is due.
+    phase: f32,
+    gate: f32,
+    playing: Option<ArpNote>,
+}
+
+impl Default for NoteSequencer {
+    fn default() -> NoteSequencer {
+        NoteSequencer {
+            keys: [(0, 0); MAX_KEYS],
+            len: 0,
+            step: 0,
+            phase: 1.0,
+            gate: 0.0,
+            playing: None,
+        }
+    }
+}
+
+impl NoteSequencer {
+    pub fn press(&mut self, channel: u8, note: u8) {
+        self.release(channel, note);
+        // The first key starts the pattern from step one, straight away.
+        if self.len == 0 {
+            self.step = 0;
+            self.phase = 1.0;
+        }
+        if self.len == MAX_KEYS {
+            self.keys.copy_within(1.., 0);
+            self.len -= 1;
+        }
+        self.keys[self.len] = (channel, note);
+        self.len += 1;
+    }
+
+    pub fn release(&mut self, channel: u8, note: u8) {
+        let keys = &self.keys[..self.len];
+        if let Some(index) = keys.iter().position(|&key| key == (channel, note)) {
+            self.keys.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+
+    // Goes back to the first step, which plays at once if keys are down.
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.phase = 1.0;
+    }
+
+    // Forgets every key and returns the note left sounding, which needs a note-off.
+    pub fn stop(&mut self) -> Option<ArpNote> {
+        self.len = 0;
+        self.playing.take()
+    }
+
+    // Advances by one sample.
+    pub fn next(
+        &mut self,
+        steps: &[NoteStep; NOTE_STEPS],
+        rate_hz: f32,
+        per_sample: f32,
+    ) -> ArpStep {
+        let mut out = ArpStep::default();
+        if self.len == 0 {
+            out.off = self.playing.take();
+            return out;
+        }
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            out.off = self.playing.take();
+            let step = steps[self.step];
+            let (channel, key) = self.keys[self.len - 1];
+            let note = key as i32 + step.pitch;
+            if step.gate > 0.0 && (0..=127).contains(&note) {
+                self.playing = Some(ArpNote {
+                    channel,
+                    note: note as u8,
+                    velocity: step.velocity,
+                });
+            }
+            out.on = self.playing;
+            self.gate = step.gate;
+            self.step = (self.step + 1) % NOTE_STEPS;
+        } else if self.phase >= self.gate {
+            out.off = self.playing.take();
+        }
+        self.phase += rate_hz * per_sample;
+        out
+    }
+}
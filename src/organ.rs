@@ -0,0 +1,32 @@
+pub const DRAWBARS: usize = 9;
+
+pub const FOOTAGES: [&str; DRAWBARS] = [
+    "16'", "5 1/3'", "8'", "4'", "2 2/3'", "2'", "1 3/5'", "1 1/3'", "1'",
+];
+
+// Harmonic of the 16' pitch (an octave below the played note) sounded by each drawbar.
+const HARMONICS: [usize; DRAWBARS] = [1, 3, 2, 4, 6, 8, 10, 12, 16];
+
+pub const HIGHEST_HARMONIC: usize = 16;
+
+// Drawbars have nine positions, 0 (off) to 8 (full).
+pub fn drawbar_step(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 8.0).round() as u8
+}
+
+// Each step below full is roughly 3 dB quieter, as on a tonewheel organ.
+fn drawbar_gain(step: u8) -> f32 {
+    if step == 0 {
+        0.0
+    } else {
+        10.0f32.powf(-3.0 * (8 - step) as f32 / 20.0)
+    }
+}
+
+pub fn amplitudes(drawbars: &[f32; DRAWBARS]) -> [f32; HIGHEST_HARMONIC] {
+    let mut amplitudes = [0.0; HIGHEST_HARMONIC];
+    for (&harmonic, &value) in HARMONICS.iter().zip(drawbars) {
+        amplitudes[harmonic - 1] += drawbar_gain(drawbar_step(value));
+    }
+    amplitudes
+}
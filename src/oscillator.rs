@@ -0,0 +1,185 @@
+use crate::random::Random;
+use crate::sine;
+use crate::wavetable::{self, Interpolation};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+    Pulse,
+    Noise,
+    Wavetable,
+    PhaseDistortion,
+}
+
+impl Waveform {
+    const ALL: [Waveform; 8] = [
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Saw,
+        Waveform::Square,
+        Waveform::Pulse,
+        Waveform::Noise,
+        Waveform::Wavetable,
+        Waveform::PhaseDistortion,
+    ];
+
+    pub fn from_param(value: f32) -> Waveform {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    // Centre of the parameter range that selects this waveform.
+    pub fn to_param(self) -> f32 {
+        let index = Self::ALL.iter().position(|&w| w == self).unwrap_or(0);
+        (index as f32 + 0.5) / Self::ALL.len() as f32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::Triangle => "Triangle",
+            Waveform::Saw => "Saw",
+            Waveform::Square => "Square",
+            Waveform::Pulse => "Pulse",
+            Waveform::Noise => "Noise",
+            Waveform::Wavetable => "Wavetable",
+            Waveform::PhaseDistortion => "Phase Dist",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SubShape {
+    Sine,
+    Square,
+}
+
+impl SubShape {
+    pub fn from_param(value: f32) -> SubShape {
+        if value < 0.5 {
+            SubShape::Sine
+        } else {
+            SubShape::Square
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.waveform().name()
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        match self {
+            SubShape::Sine => Waveform::Sine,
+            SubShape::Square => Waveform::Square,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum PwmSource {
+    #[default]
+    Lfo,
+    Envelope,
+}
+
+impl PwmSource {
+    pub fn from_param(value: f32) -> PwmSource {
+        if value < 0.5 {
+            PwmSource::Lfo
+        } else {
+            PwmSource::Envelope
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PwmSource::Lfo => "LFO",
+            PwmSource::Envelope => "Envelope",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct OscillatorSettings {
+    pub waveform: Waveform,
+    pub pulse_width: f32,
+    pub pd_amount: f32,
+    pub wt_position: f32,
+    pub wt_interpolation: Interpolation,
+}
+
+impl OscillatorSettings {
+    // `phase` is the position within the current cycle, in [0, 1), and `phase_inc` the
+    // amount it advances per sample. Discontinuities are smoothed with PolyBLEP (steps)
+    // and PolyBLAMP (corners) so the shapes stay band-limited at any pitch.
+    pub fn sample(&self, phase: f32, phase_inc: f32, rng: &mut Random) -> f32 {
+        match self.waveform {
+            Waveform::Sine => sine::sine(phase),
+            Waveform::Triangle => {
+                let trough = (phase + 0.25).fract();
+                let peak = (phase + 0.75).fract();
+                let naive = 1.0 - 4.0 * (trough - 0.5).abs();
+                let corners = poly_blamp(trough, phase_inc) - poly_blamp(peak, phase_inc);
+                naive + 4.0 * phase_inc * corners
+            }
+            Waveform::Saw => 2.0 * phase - 1.0 - poly_blep(phase, phase_inc),
+            Waveform::Square => {
+                let naive = if phase < 0.5 { 1.0 } else { -1.0 };
+                naive + poly_blep(phase, phase_inc) - poly_blep((phase + 0.5).fract(), phase_inc)
+            }
+            Waveform::Pulse => {
+                let width = self.pulse_width;
+                let naive = if phase < width { 1.0 } else { -1.0 };
+                let falling = (phase + 1.0 - width).fract();
+                naive + poly_blep(phase, phase_inc) - poly_blep(falling, phase_inc)
+            }
+            Waveform::Noise => rng.bipolar(),
+            Waveform::Wavetable => wavetable::builtin().sample(
+                phase,
+                phase_inc,
+                self.wt_position,
+                self.wt_interpolation,
+            ),
+            Waveform::PhaseDistortion => {
+                // CZ-style: the first half of the cosine cycle is squeezed into
+                // [0, knee) and the second half stretched over the rest, bending the
+                // cosine towards a saw as the knee moves left.
+                let knee = (0.5 - 0.49 * self.pd_amount).max(2.0 * phase_inc).min(0.5);
+                let warped = if phase < knee {
+                    0.5 * phase / knee
+                } else {
+                    0.5 + 0.5 * (phase - knee) / (1.0 - knee)
+                };
+                -sine::cosine(warped)
+            }
+        }
+    }
+}
+
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = t / dt;
+        x + x - x * x - 1.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt;
+        x * x + x + x + 1.0
+    } else {
+        0.0
+    }
+}
+
+fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = t / dt - 1.0;
+        -x * x * x / 3.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt + 1.0;
+        x * x * x / 3.0
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,75 @@
+use crate::random::Random;
+
+// Longest delay line per voice; enough for ~11 Hz at 96 kHz.
+const MAX_DELAY: usize = 8192;
+
+pub struct PluckString {
+    buffer: Vec<f32>,
+    write: usize,
+    delay: f32,
+    damping: f32,
+    previous_tap: f32,
+    rng: Random,
+}
+
+impl Default for PluckString {
+    fn default() -> PluckString {
+        PluckString {
+            buffer: vec![0.0; MAX_DELAY],
+            write: 0,
+            delay: 1.0,
+            damping: 0.5,
+            previous_tap: 0.0,
+            rng: Random::default(),
+        }
+    }
+}
+
+impl PluckString {
+    // Sets the loop to `freq`. Called every control block, so bends, glide and vibrato
+    // reach a string that is already ringing.
+    pub fn tune(&mut self, freq: f32, sample_rate: f32) {
+        let period = (sample_rate / freq).clamp(2.0, (MAX_DELAY - 2) as f32);
+        // The two-tap damping filter adds `damping` samples of delay to the loop.
+        self.delay = period - self.damping;
+    }
+
+    // Fill one period of the delay line, at the last tuning, with a noise burst, darker
+    // for low `brightness`.
+    pub fn excite(&mut self, brightness: f32) {
+        let period = self.delay + self.damping;
+        self.damping = 0.5 * (1.0 - brightness);
+        self.delay = period - self.damping;
+
+        self.buffer.iter_mut().for_each(|sample| *sample = 0.0);
+        let smoothing = 0.9 * (1.0 - brightness);
+        let mut state = 0.0;
+        for i in 0..period as usize {
+            let white = self.rng.bipolar();
+            state = white + (state - white) * smoothing;
+            self.buffer[(self.write + MAX_DELAY - 1 - i) % MAX_DELAY] = state;
+        }
+        self.previous_tap = 0.0;
+    }
+
+    // `feedback` is the gain applied once per trip around the loop.
+    pub fn next(&mut self, feedback: f32) -> f32 {
+        let read = self.write as f32 + MAX_DELAY as f32 - self.delay;
+        let index = read as usize;
+        let frac = read - read.floor();
+        let y0 = self.buffer[index % MAX_DELAY];
+        let y1 = self.buffer[(index + 1) % MAX_DELAY];
+        let tap = y0 + (y1 - y0) * frac;
+
+        let out = tap * (1.0 - self.damping) + self.previous_tap * self.damping;
+        self.previous_tap = tap;
+        self.buffer[self.write] = out * feedback;
+        self.write = (self.write + 1) % MAX_DELAY;
+        out
+    }
+}
+
+// Loop gain that makes a string at `freq` fall by 60 dB over `decay` seconds.
+pub fn feedback_for_decay(freq: f32, decay: f32) -> f32 {
+    0.001f32.powf(1.0 / (freq * decay))
+}
@@ -0,0 +1,177 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use vst::util::AtomicFloat;
+
+use crate::{descriptor, state};
+
+pub const PROGRAMS: usize = 32;
+
+// Built into the binary, in the order they fill the first slots of a new instance.
+const FACTORY: [&str; 10] = [
+    include_str!("../presets/acid_bass.toml"),
+    include_str!("../presets/sub_bass.toml"),
+    include_str!("../presets/sync_lead.toml"),
+    include_str!("../presets/supersaw_lead.toml"),
+    include_str!("../presets/warm_pad.toml"),
+    include_str!("../presets/choir_pad.toml"),
+    include_str!("../presets/fm_piano.toml"),
+    include_str!("../presets/drawbar_organ.toml"),
+    include_str!("../presets/noise_riser.toml"),
+    include_str!("../presets/random_blips.toml"),
+];
+
+pub const INIT_NAME: &str = "Init";
+
+pub struct Program {
+    pub name: Mutex<String>,
+    values: Vec<AtomicFloat>,
+}
+
+impl Program {
+    fn init() -> Program {
+        let (name, values) = init();
+        Program {
+            name: Mutex::new(name),
+            values: values.into_iter().map(AtomicFloat::new).collect(),
+        }
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.values.iter().map(|value| value.get())
+    }
+
+    pub fn store(&self, values: impl Iterator<Item = f32>) {
+        for (slot, value) in self.values.iter().zip(values) {
+            slot.set(value);
+        }
+    }
+}
+
+// The initial program's name and values: every parameter at its default.
+pub fn init() -> (String, Vec<f32>) {
+    let values = descriptor::table().iter().map(|descriptor| descriptor.default).collect();
+    (INIT_NAME.to_string(), values)
+}
+
+// The factory presets' names and values. They're written as typed text, which takes a
+// while to read, so that happens once and every instance shares the result.
+pub fn factory() -> &'static [(String, Vec<f32>)] {
+    static PRESETS: OnceLock<Vec<(String, Vec<f32>)>> = OnceLock::new();
+    PRESETS.get_or_init(|| {
+        FACTORY.iter().filter_map(|preset| state::read_preset(preset.as_bytes())).collect()
+    })
+}
+
+// The user's preset folder, where the platform keeps per-user presets or data.
+#[cfg(target_os = "macos")]
+pub fn user_folder() -> Option<PathBuf> {
+    let home = env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Audio/Presets/Rust Synth"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn user_folder() -> Option<PathBuf> {
+    let app_data = env::var_os("APPDATA")?;
+    Some(PathBuf::from(app_data).join("Rust Synth").join("Presets"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn user_folder() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+    let data = data.or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".local/share")))?;
+    Some(data.join("rust-synth").join("presets"))
+}
+
+// The presets in the user's folder, in file name order. Files that don't read as presets
+// are skipped. This touches the disk, so it's for the UI thread, never the audio thread.
+pub fn user_presets() -> Vec<(String, Vec<f32>)> {
+    let Some(entries) = user_folder().and_then(|folder| fs::read_dir(folder).ok()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = (entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .collect();
+    paths.sort();
+    (paths.iter())
+        .filter_map(|path| fs::read(path).ok())
+        .filter_map(|data| state::read_preset(&data))
+        .collect()
+}
+
+// Writes a preset into the user's folder as `<name>.toml`. A file already there is only
+// replaced if `replace` says so; otherwise saving fails. Like `user_presets`, only for
+// the UI thread.
+pub fn save_user_preset(name: &str, values: &[f32], replace: bool) -> io::Result<PathBuf> {
+    let folder = user_folder().ok_or(io::ErrorKind::NotFound)?;
+    fs::create_dir_all(&folder)?;
+    let path = folder.join(format!("{}.toml", file_name(name)));
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(replace)
+        .create_new(!replace)
+        .open(&path)?;
+    file.write_all(state::preset_file(name, values).as_bytes())?;
+    Ok(path)
+}
+
+// `name` with anything a file system might refuse replaced.
+fn file_name(name: &str) -> String {
+    let name: String = (name.trim().chars())
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => INIT_NAME.to_string(),
+        name => name.to_string(),
+    }
+}
+
+// The host's program slots. The current program's values are the live parameters, so
+// edits belong to it; they're copied back into its slot when another is chosen. Values
+// are atomics and sized up front, so choosing a program from the audio thread, as a
+// program change does, neither locks nor allocates.
+pub struct Bank {
+    programs: Vec<Program>,
+    current: AtomicI32,
+}
+
+impl Default for Bank {
+    fn default() -> Bank {
+        Bank {
+            programs: (0..PROGRAMS).map(|_| Program::init()).collect(),
+            current: AtomicI32::new(0),
+        }
+    }
+}
+
+impl Bank {
+    pub fn get(&self, index: i32) -> Option<&Program> {
+        usize::try_from(index).ok().and_then(|index| self.programs.get(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Program> {
+        self.programs.iter()
+    }
+
+    pub fn current_index(&self) -> i32 {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    pub fn current(&self) -> &Program {
+        &self.programs[self.current_index() as usize]
+    }
+
+    pub fn set_current(&self, index: i32) {
+        self.current.store(index, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Xorshift noise for the audio thread: no allocation, no locks and no system calls.
+// Everything that draws owns one, seeded once from a shared counter so that no two
+// voices or instances run in step.
+#[derive(Clone, Copy)]
+pub struct Random {
+    state: u32,
+}
+
+impl Default for Random {
+    fn default() -> Random {
+        static SEEDS: AtomicU32 = AtomicU32::new(0);
+        let count = SEEDS.fetch_add(1, Ordering::Relaxed);
+        // Murmur3's finaliser, to scatter neighbouring counts across the state space.
+        let mut seed = count.wrapping_add(1).wrapping_mul(0x9e37_79b9);
+        seed ^= seed >> 16;
+        seed = seed.wrapping_mul(0x85eb_ca6b);
+        seed ^= seed >> 13;
+        seed = seed.wrapping_mul(0xc2b2_ae35);
+        seed ^= seed >> 16;
+        // Xorshift never leaves an all-zero state.
+        Random { state: seed.max(1) }
+    }
+}
+
+impl Random {
+    fn next(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    // From 0 up to but not including 1.
+    pub fn unit(&mut self) -> f32 {
+        (self.next() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // From -1 up to but not including 1.
+    pub fn bipolar(&mut self) -> f32 {
+        self.unit() * 2.0 - 1.0
+    }
+}
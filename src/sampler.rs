@@ -0,0 +1,169 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// MIDI note at which a sample plays back at its recorded pitch.
+pub const ROOT_NOTE: u8 = 60;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+pub struct Sample {
+    pub path: PathBuf,
+    pub sample_rate: f32,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+impl Sample {
+    pub fn load(path: &Path) -> io::Result<Sample> {
+        let bytes = fs::read(path)?;
+        let (sample_rate, left, right) =
+            parse_wav(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Sample {
+            path: path.to_path_buf(),
+            sample_rate,
+            left,
+            right,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn frame(&self, position: f64) -> (f32, f32) {
+        let index = position as usize;
+        let frac = (position - index as f64) as f32;
+        let next = (index + 1).min(self.len() - 1);
+        let lerp = |data: &[f32]| data[index] + (data[next] - data[index]) * frac;
+        (lerp(&self.left), lerp(&self.right))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SamplerVoice {
+    position: f64,
+    finished: bool,
+}
+
+impl SamplerVoice {
+    pub fn start(&mut self, sample: &Sample, start: f32) {
+        self.position = start_frame(sample, start);
+        self.finished = sample.len() == 0;
+    }
+
+    // `rate` is how many sample frames to advance per output sample. When `looping`,
+    // playback wraps from the end back to the start point.
+    pub fn next(&mut self, sample: &Sample, rate: f64, start: f32, looping: bool) -> (f32, f32) {
+        if self.finished || self.position >= sample.len() as f64 {
+            self.finished = true;
+            return (0.0, 0.0);
+        }
+
+        let out = sample.frame(self.position);
+        self.position += rate;
+        if self.position >= sample.len() as f64 {
+            if looping {
+                let loop_start = start_frame(sample, start);
+                let loop_length = (sample.len() as f64 - loop_start).max(1.0);
+                self.position = loop_start + (self.position - loop_start) % loop_length;
+            } else {
+                self.finished = true;
+            }
+        }
+        out
+    }
+}
+
+fn start_frame(sample: &Sample, start: f32) -> f64 {
+    (start.clamp(0.0, 1.0) as f64 * sample.len() as f64).min(sample.len().saturating_sub(1) as f64)
+}
+
+// Decodes a RIFF/WAVE file holding 16/24/32-bit integer or 32-bit float PCM into
+// separate left/right channels; mono files are duplicated to both sides.
+fn parse_wav(bytes: &[u8]) -> Result<(f32, Vec<f32>, Vec<f32>), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a RIFF/WAVE file".to_string());
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(bytes, offset + 4) as usize;
+        let body = &bytes[offset + 8..(offset + 8 + size).min(bytes.len())];
+        match id {
+            b"fmt " => format = Some(body),
+            b"data" => data = Some(body),
+            _ => (),
+        }
+        // Chunks are padded to an even length.
+        offset += 8 + size + (size & 1);
+    }
+
+    let format = format.ok_or("missing fmt chunk")?;
+    let data = data.ok_or("missing data chunk")?;
+    if format.len() < 16 {
+        return Err("truncated fmt chunk".to_string());
+    }
+
+    let mut tag = read_u16(format, 0);
+    let channels = read_u16(format, 2) as usize;
+    let sample_rate = read_u32(format, 4) as f32;
+    let bits = read_u16(format, 14);
+    if tag == FORMAT_EXTENSIBLE && format.len() >= 26 {
+        tag = read_u16(format, 24);
+    }
+    if channels == 0 || sample_rate <= 0.0 {
+        return Err("invalid channel count or sample rate".to_string());
+    }
+
+    let bytes_per_sample = (bits / 8) as usize;
+    let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+        (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (FORMAT_PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0,
+        (FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+        (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        _ => return Err(format!("unsupported format {} with {} bits", tag, bits)),
+    };
+
+    let frame_size = bytes_per_sample * channels;
+    let frames = data.len() / frame_size;
+    let mut left = Vec::with_capacity(frames);
+    let mut right = Vec::with_capacity(frames);
+    for frame in data.chunks_exact(frame_size) {
+        let l = decode(&frame[..bytes_per_sample]);
+        let r = if channels > 1 {
+            decode(&frame[bytes_per_sample..bytes_per_sample * 2])
+        } else {
+            l
+        };
+        left.push(l);
+        right.push(r);
+    }
+
+    Ok((sample_rate, left, right))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
@@ -0,0 +1,65 @@
+use crate::{cutoff_from_param, drive_from_param, RustSynthParameters};
+
+// Time constant, in seconds, for a smoothed parameter to follow a jump.
+const SMOOTHING_SECONDS: f32 = 0.02;
+
+// The audible parameters as the voices hear them.
+#[derive(Clone, Copy, Default)]
+pub struct SmoothedParameters {
+    pub cutoff_hz: f32,
+    pub resonance: f32,
+    pub drive_gain: f32,
+    pub osc_mix: f32,
+    pub sub_level: f32,
+    pub noise_level: f32,
+    pub ring_mod: f32,
+}
+
+// Eases the audible parameters toward the host's values once per control block, so
+// automation and fast knob moves glide rather than step. Cutoff and drive are smoothed
+// before they're mapped, so a cutoff sweep moves evenly in pitch.
+#[derive(Default)]
+pub struct ParameterSmoother {
+    values: [f32; 7],
+    // Cleared until the first update, which starts at the targets rather than gliding
+    // from zero.
+    primed: bool,
+}
+
+impl ParameterSmoother {
+    pub fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    // `seconds` is the time since the last call.
+    pub fn next(&mut self, params: &RustSynthParameters, seconds: f32) -> SmoothedParameters {
+        let targets = [
+            params.cutoff.get(),
+            params.resonance.get(),
+            params.drive.get(),
+            params.osc_mix.get(),
+            params.sub_level.get(),
+            params.noise_level.get(),
+            params.ring_mod.get(),
+        ];
+        if self.primed {
+            let coeff = 1.0 - (-seconds / SMOOTHING_SECONDS).exp();
+            for (value, target) in self.values.iter_mut().zip(targets) {
+                *value += (target - *value) * coeff;
+            }
+        } else {
+            self.values = targets;
+            self.primed = true;
+        }
+        let [cutoff, resonance, drive, osc_mix, sub_level, noise_level, ring_mod] = self.values;
+        SmoothedParameters {
+            cutoff_hz: cutoff_from_param(cutoff),
+            resonance,
+            drive_gain: drive_from_param(drive),
+            osc_mix,
+            sub_level,
+            noise_level,
+            ring_mod,
+        }
+    }
+}
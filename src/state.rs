@@ -0,0 +1,269 @@
+// The state the host saves with a project, for one program or the whole bank: every
+// parameter but MIDI Learn, plus what lives outside them, namely the sample, the learned
+// chord and the CC bindings. It's written as a small subset of TOML so it can be read by hand:
+//
+//   version = 1
+//   program = 2                     (banks only: the current program)
+//   sample = "/path/to/sample.wav"  (only while one is loaded)
+//   learned_chord = 145
+//
+//   [cc]                            (controller = parameter index)
+//   74 = 94
+//
+//   [program]                       (a bank has [program.0] and so on, one per slot)
+//   name = "Init"
+//   0 = 0.90909094
+//   1 = 0.1
+//
+// Parameters are keyed by index and hold their normalized values, written exactly. Read
+// back, a parameter can also be keyed by its full name, and its value given as the text
+// typed into the host's field, which is how the factory presets are written:
+//
+//   "Filter: Cutoff" = "800 Hz"
+//   "Osc: Waveform" = "Saw"
+//
+// Parameters a state leaves out take their defaults and keys it doesn't know are
+// skipped, so states from older and newer builds still load. MIDI Learn is never written
+// or read, so loading a state saved while learning doesn't arm it.
+
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::chord::Shape;
+use crate::descriptor::{self, MIDI_LEARN};
+use crate::program::INIT_NAME;
+use crate::sampler::Sample;
+use crate::{Patch, RustSynthParameters, PARAMETERS, UNBOUND};
+
+const VERSION: i32 = 1;
+
+pub fn save_preset(params: &RustSynthParameters) -> String {
+    let mut out = String::new();
+    write_globals(&mut out, params, None);
+    let name = params.programs.current().name.lock().unwrap().clone();
+    write_program(&mut out, "program", &name, live_values(params));
+    out
+}
+
+pub fn save_bank(params: &RustSynthParameters) -> String {
+    let mut out = String::new();
+    let current = params.programs.current_index();
+    write_globals(&mut out, params, Some(current));
+    for (index, program) in (0..).zip(params.programs.iter()) {
+        let name = program.name.lock().unwrap().clone();
+        // The current program's slot is only brought up to date when it's left.
+        let values = match index == current {
+            true => live_values(params),
+            false => program.values().collect(),
+        };
+        write_program(&mut out, &format!("program.{}", index), &name, values);
+    }
+    out
+}
+
+// A preset file for the user's folder. It holds a single program, keyed by full name
+// rather than index so it still reads after parameters are added or moved, and leaves
+// out the parameters with no value to save.
+pub fn preset_file(name: &str, values: &[f32]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "version = {}\n\n[program]\nname = {}", VERSION, quote(name));
+    for (index, (descriptor, value)) in (0..).zip(descriptor::table().iter().zip(values)) {
+        if descriptor.value.is_some() && index != MIDI_LEARN {
+            let _ = writeln!(out, "{} = {:?}", quote(&descriptor.full_name()), value);
+        }
+    }
+    out
+}
+
+// The name and values of a single preset, without its sample, chord or CC bindings.
+pub fn read_preset(data: &[u8]) -> Option<(String, Vec<f32>)> {
+    parse(data).map(|entries| read_program(&entries, "program"))
+}
+
+// Loads into the current program. Anything that isn't one of these states is ignored.
+pub fn load_preset(params: &RustSynthParameters, data: &[u8]) {
+    let Some(entries) = parse(data) else {
+        return;
+    };
+    load_globals(params, &entries);
+    let (name, values) = read_program(&entries, "program");
+    *params.programs.current().name.lock().unwrap() = name;
+    params.restore(values.into_iter());
+}
+
+// Replaces every slot; those the bank leaves out go back to the initial program.
+pub fn load_bank(params: &RustSynthParameters, data: &[u8]) {
+    let Some(entries) = parse(data) else {
+        return;
+    };
+    load_globals(params, &entries);
+    for (index, program) in (0..).zip(params.programs.iter()) {
+        let (name, values) = read_program(&entries, &format!("program.{}", index));
+        *program.name.lock().unwrap() = name;
+        program.store(values.into_iter());
+    }
+    let current = entries.iter().find(|entry| entry.section.is_empty() && entry.key == "program");
+    let current = current.and_then(|entry| entry.value.parse().ok());
+    let current = current.filter(|&index| params.programs.get(index).is_some()).unwrap_or(0);
+    params.programs.set_current(current);
+    params.restore(params.programs.current().values());
+}
+
+fn live_values(params: &RustSynthParameters) -> Vec<f32> {
+    params.program_values().collect()
+}
+
+fn write_globals(out: &mut String, params: &RustSynthParameters, current: Option<i32>) {
+    let _ = writeln!(out, "version = {}", VERSION);
+    if let Some(current) = current {
+        let _ = writeln!(out, "program = {}", current);
+    }
+    if let Some(sample) = params.sample.lock().unwrap().as_ref() {
+        let _ = writeln!(out, "sample = {}", quote(&sample.path.to_string_lossy()));
+    }
+    let _ = writeln!(out, "learned_chord = {}", params.learned_chord.load(Ordering::Relaxed));
+    let _ = writeln!(out, "\n[cc]");
+    for (controller, binding) in params.cc_bindings.iter().enumerate() {
+        match binding.load(Ordering::Relaxed) {
+            UNBOUND => {}
+            index => {
+                let _ = writeln!(out, "{} = {}", controller, index);
+            }
+        }
+    }
+}
+
+fn write_program(out: &mut String, section: &str, name: &str, values: Vec<f32>) {
+    let _ = writeln!(out, "\n[{}]\nname = {}", section, quote(name));
+    for (index, value) in (0..).zip(&values).filter(|&(index, _)| index != MIDI_LEARN) {
+        // Debug formatting is the shortest text that reads back as the same value, and keeps
+        // a decimal point so TOML reads it as a float.
+        let _ = writeln!(out, "{} = {:?}", index, value);
+    }
+}
+
+fn load_globals(params: &RustSynthParameters, entries: &[Entry]) {
+    let globals = || entries.iter().filter(|entry| entry.section.is_empty());
+    let sample = globals().find(|entry| entry.key == "sample");
+    let sample = sample.and_then(|entry| unquote(entry.value));
+    *params.sample.lock().unwrap() =
+        sample.and_then(|path| Sample::load(Path::new(&path)).ok()).map(Arc::new);
+    let chord = globals().find(|entry| entry.key == "learned_chord");
+    let chord = chord.and_then(|entry| entry.value.parse().ok()).unwrap_or(Shape::MAJOR.0);
+    params.learned_chord.store(chord, Ordering::Relaxed);
+    for binding in &params.cc_bindings {
+        binding.store(UNBOUND, Ordering::Relaxed);
+    }
+    for entry in entries.iter().filter(|entry| entry.section == "cc") {
+        let controller = entry.key.parse::<usize>().ok();
+        let binding = controller.and_then(|controller| params.cc_bindings.get(controller));
+        let index = entry.value.parse::<i32>().ok().filter(|index| (0..PARAMETERS).contains(index));
+        if let (Some(binding), Some(index)) = (binding, index) {
+            binding.store(index, Ordering::Relaxed);
+        }
+    }
+}
+
+// A program's name and all its values, defaults filling any gaps. Entries are applied
+// in order, so typed text reads as it would with the entries before it already set, like
+// an LFO rate after its sync.
+fn read_program(entries: &[Entry], section: &str) -> (String, Vec<f32>) {
+    let mut name = None;
+    let program = Patch::default();
+    for entry in entries.iter().filter(|entry| entry.section == section) {
+        if entry.key == "name" {
+            name = unquote(entry.value);
+            continue;
+        }
+        let index = match unquote(entry.key) {
+            Some(full_name) => descriptor::index_of(&full_name),
+            None => entry.key.parse().ok(),
+        };
+        let index = index.filter(|&index| (0..PARAMETERS).contains(&index) && index != MIDI_LEARN);
+        let Some(index) = index else {
+            continue;
+        };
+        let value = match unquote(entry.value) {
+            Some(text) => descriptor::parse(&program, index, &text),
+            None => entry.value.parse().ok(),
+        };
+        if let Some(value) = value {
+            program.set(index, f32::clamp(value, 0.0, 1.0));
+        }
+    }
+    let values = (0..PARAMETERS).map(|index| program.get(index)).collect();
+    (name.unwrap_or_else(|| INIT_NAME.to_string()), values)
+}
+
+struct Entry<'a> {
+    section: &'a str,
+    key: &'a str,
+    value: &'a str,
+}
+
+// The `key = value` lines under their table headers. `None` unless the data starts with
+// a version this build can read.
+fn parse(data: &[u8]) -> Option<Vec<Entry<'_>>> {
+    let text = std::str::from_utf8(data).ok()?;
+    let mut section = "";
+    let mut entries = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = header.trim();
+        } else if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim();
+            // Strings keep everything to their closing quote; other values stop at a comment.
+            let value = match value.starts_with('"') {
+                true => value,
+                false => value.split('#').next().unwrap_or_default().trim_end(),
+            };
+            entries.push(Entry { section, key: key.trim(), value });
+        }
+    }
+    let first = entries.first().filter(|entry| entry.section.is_empty());
+    let version = first.filter(|entry| entry.key == "version")?.value.parse::<i32>().ok()?;
+    (1..=VERSION).contains(&version).then_some(entries)
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// The text of a quoted string, up to its closing quote.
+fn unquote(value: &str) -> Option<String> {
+    let mut chars = value.strip_prefix('"')?.chars();
+    let mut text = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    text.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)?);
+                }
+                c => text.push(c),
+            },
+            c => text.push(c),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+const MAX_SCHEDULED: usize = 256;
+
+// A note-on, or a note-off when `velocity` is `None`, due at `due` samples.
+#[derive(Clone, Copy, Default)]
+pub struct Scheduled {
+    due: u64,
+    pub channel: u8,
+    pub note: u8,
+    pub velocity: Option<u8>,
+}
+
+// Holds note-ons back by a few milliseconds, so the notes of a chord can be spread out
+// and their timing roughened. A note let go before it has started is released just
+// after it starts, rather than lost.
+pub struct Strum {
+    // Soonest first; events due together keep their order.
+    queue: [Scheduled; MAX_SCHEDULED],
+    len: usize,
+    // Samples since the plugin started.
+    clock: u64,
+    // Note-ons seen so far on this sample, for the strum to space out.
+    chord_notes: u64,
+}
+
+impl Default for Strum {
+    fn default() -> Strum {
+        Strum {
+            queue: [Scheduled::default(); MAX_SCHEDULED],
+            len: 0,
+            clock: 0,
+            chord_notes: 0,
+        }
+    }
+}
+
+impl Strum {
+    // Delays a note-on by `gap` samples for each note already started on this sample,
+    // plus `jitter`. Returns false if it should just be played now.
+    pub fn on(&mut self, channel: u8, note: u8, velocity: u8, gap: u64, jitter: u64) -> bool {
+        let delay = gap * self.chord_notes + jitter;
+        self.chord_notes += 1;
+        delay > 0
+            && self.insert(Scheduled {
+                due: self.clock + delay,
+                channel,
+                note,
+                velocity: Some(velocity),
+            })
+    }
+
+    // Returns false if the note isn't waiting to start, so it should be released now.
+    pub fn off(&mut self, channel: u8, note: u8) -> bool {
+        let queued = &self.queue[..self.len];
+        let Some(index) = queued
+            .iter()
+            .rposition(|s| s.channel == channel && s.note == note && s.velocity.is_some())
+        else {
+            return false;
+        };
+        let on = self.queue[index];
+        if !self.insert(Scheduled { velocity: None, ..on }) {
+            // No room to release it later, so it never starts.
+            self.queue.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+        true
+    }
+
+    // The next event due by now, if any. Call until it returns `None`, then `advance`.
+    pub fn next(&mut self) -> Option<Scheduled> {
+        if self.len == 0 || self.queue[0].due > self.clock {
+            return None;
+        }
+        let event = self.queue[0];
+        self.queue.copy_within(1..self.len, 0);
+        self.len -= 1;
+        Some(event)
+    }
+
+    pub fn advance(&mut self) {
+        self.clock += 1;
+        self.chord_notes = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn insert(&mut self, event: Scheduled) -> bool {
+        if self.len == MAX_SCHEDULED {
+            return false;
+        }
+        let queued = &self.queue[..self.len];
+        let index = queued.iter().rposition(|s| s.due <= event.due).map_or(0, |i| i + 1);
+        self.queue.copy_within(index..self.len, index + 1);
+        self.queue[index] = event;
+        self.len += 1;
+        true
+    }
+}
@@ -0,0 +1,68 @@
+// Patch dumps as SysEx, for librarians and for copying a sound between instances.
+//
+//   Request: F0 7D 01 F7
+//   Dump:    F0 7D 02 <count: 2 bytes> <value: 3 bytes>... <checksum> F7
+//
+// 7D is the manufacturer ID set aside for non-commercial use. Multi-byte numbers are
+// seven bits per byte, most significant first. Each value is a normalized parameter
+// scaled to 21 bits, in index order, and the checksum is the low seven bits of the sum
+// of every byte between the count and the checksum.
+
+const MANUFACTURER: u8 = 0x7D;
+const DUMP_REQUEST: u8 = 0x01;
+const DUMP: u8 = 0x02;
+const VALUE_MAX: u32 = (1 << 21) - 1;
+
+pub enum SysEx<'a> {
+    DumpRequest,
+    // The encoded values, already checked against the count and checksum.
+    Dump(&'a [u8]),
+}
+
+// Reads a payload with or without its F0 and F7 framing. Anything not addressed to this
+// synth, or damaged in transit, gives `None`.
+pub fn parse(payload: &[u8]) -> Option<SysEx<'_>> {
+    let payload = payload.strip_prefix(&[0xF0]).unwrap_or(payload);
+    let payload = payload.strip_suffix(&[0xF7]).unwrap_or(payload);
+    match payload {
+        [MANUFACTURER, DUMP_REQUEST] => Some(SysEx::DumpRequest),
+        [MANUFACTURER, DUMP, count_high, count_low, values @ .., checksum] => {
+            let count = (*count_high as usize) << 7 | *count_low as usize;
+            let sum = values.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            let valid = values.len() == count * 3 && sum & 0x7F == *checksum;
+            valid.then_some(SysEx::Dump(values))
+        }
+        _ => None,
+    }
+}
+
+// The parameter values held in a dump.
+pub fn values(encoded: &[u8]) -> impl Iterator<Item = f32> + '_ {
+    encoded.chunks_exact(3).map(|bytes| {
+        let value = (bytes[0] as u32) << 14 | (bytes[1] as u32) << 7 | bytes[2] as u32;
+        value as f32 / VALUE_MAX as f32
+    })
+}
+
+// Writes a complete dump, F0 to F7, into `out`, replacing what was there. `out` only
+// grows if it lacks the capacity.
+pub fn encode_dump(values: impl ExactSizeIterator<Item = f32>, out: &mut Vec<u8>) {
+    let count = values.len();
+    out.clear();
+    out.extend_from_slice(&[0xF0, MANUFACTURER, DUMP, (count >> 7) as u8 & 0x7F]);
+    out.push(count as u8 & 0x7F);
+    let mut sum = 0u8;
+    for value in values {
+        let value = (value.clamp(0.0, 1.0) * VALUE_MAX as f32).round() as u32;
+        for byte in [(value >> 14) as u8, (value >> 7) as u8 & 0x7F, value as u8 & 0x7F] {
+            sum = sum.wrapping_add(byte);
+            out.push(byte);
+        }
+    }
+    out.extend_from_slice(&[sum & 0x7F, 0xF7]);
+}
+
+// Bytes in a dump of `count` values, framing included.
+pub fn dump_len(count: usize) -> usize {
+    count * 3 + 7
+}
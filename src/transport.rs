@@ -0,0 +1,63 @@
+use vst::api::TimeInfoFlags;
+use vst::host::Host;
+use vst::plugin::HostCallback;
+
+// How far, in quarter notes, the host's position can stray from where the last block
+// left it before it counts as a jump.
+const JUMP_TOLERANCE: f64 = 0.01;
+
+// The host's timeline as of the current block, for everything that keeps time with it.
+// Anything the host doesn't report keeps its last value.
+pub struct Transport {
+    // Beats per minute.
+    pub tempo: f32,
+    // Position in quarter notes at the start of the block.
+    pub ppq: f64,
+    pub playing: bool,
+}
+
+impl Default for Transport {
+    fn default() -> Transport {
+        Transport {
+            tempo: 120.0,
+            ppq: 0.0,
+            playing: false,
+        }
+    }
+}
+
+impl Transport {
+    // Returns true if playback has just started, or jumped somewhere else, such as back
+    // to the start of a loop.
+    pub fn update(&mut self, host: &HostCallback) -> bool {
+        let wanted = TimeInfoFlags::TEMPO_VALID | TimeInfoFlags::PPQ_POS_VALID;
+        let Some(info) = host.get_time_info(wanted.bits()) else {
+            return false;
+        };
+        let flags = TimeInfoFlags::from_bits_truncate(info.flags);
+        if flags.contains(TimeInfoFlags::TEMPO_VALID) && info.tempo > 0.0 {
+            self.tempo = info.tempo as f32;
+        }
+        let was_playing = self.playing;
+        let expected = self.ppq;
+        let mut jumped = false;
+        if flags.contains(TimeInfoFlags::PPQ_POS_VALID) {
+            jumped = (info.ppq_pos - expected).abs() > JUMP_TOLERANCE;
+            self.ppq = info.ppq_pos;
+        }
+        self.playing = flags.contains(TimeInfoFlags::TRANSPORT_PLAYING);
+        self.playing && (!was_playing || jumped)
+    }
+
+    // Moves the position on past a block, for hosts that don't report it every time.
+    pub fn advance(&mut self, seconds: f32) {
+        if self.playing {
+            self.ppq += (seconds * self.tempo / 60.0) as f64;
+        }
+    }
+
+    // Rate, in Hz, of something repeating every `beats` quarter notes.
+    pub fn rate_hz(&self, beats: f32) -> f32 {
+        self.tempo / 60.0 / beats
+    }
+}
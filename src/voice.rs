@@ -0,0 +1,1218 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::additive::{self, MAX_HARMONICS};
+use crate::drift::Drift;
+use crate::drum::DrumVoice;
+use crate::envelope::{Envelope, EnvelopeSettings, Retrigger};
+use crate::filter::{Filter, FilterType, FilterSettings, VoiceFilter};
+use crate::fm::{FmSettings, FmVoice, OPERATORS};
+use crate::granular::{GrainSettings, GranularVoice};
+use crate::modulation::{Destination, Modulation, SharedSources, Smoother, SourceValues};
+use crate::noise::{NoiseColor, NoiseGenerator};
+use crate::organ;
+use crate::oscillator::{OscillatorSettings, PwmSource, SubShape, Waveform};
+use crate::pluck::{self, PluckString};
+use crate::random::Random;
+use crate::sampler::{Sample, SamplerVoice, ROOT_NOTE};
+use crate::smoothing::SmoothedParameters;
+use crate::wavetable::Interpolation;
+use crate::RustSynthParameters;
+
+pub const MAX_VOICES: usize = 32;
+// Samples between updates of everything but the oscillators, amp envelope and filters,
+// about 0.36 ms at 44.1 kHz.
+pub const CONTROL_BLOCK: usize = 16;
+// Extra voices so a stolen note can fade out while its replacement starts.
+const STEAL_HEADROOM: usize = 4;
+// Seconds a stolen voice takes to fade to silence.
+const STEAL_FADE: f32 = 0.005;
+pub const MIDI_CHANNELS: usize = 16;
+// Keys remembered for mono note priority; more than this drops the oldest.
+const MAX_HELD: usize = 32;
+pub const MAX_UNISON: usize = 7;
+pub const VECTOR_SLOTS: usize = 4;
+// Semitones either side of the note at full per-note bend, as MPE specifies.
+const MPE_BEND_RANGE: f32 = 48.0;
+// Seconds for poly pressure to settle, as for channel aftertouch.
+const PRESSURE_SMOOTHING: f32 = 0.02;
+// Amp, filter, mod and one per FM operator.
+const ENVELOPES: usize = 3 + OPERATORS;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    Subtractive,
+    Fm,
+    Additive,
+    Pluck,
+    Sampler,
+    Granular,
+    Drum,
+    Organ,
+    Vector,
+}
+
+impl Engine {
+    const ALL: [Engine; 9] = [
+        Engine::Subtractive,
+        Engine::Fm,
+        Engine::Additive,
+        Engine::Pluck,
+        Engine::Sampler,
+        Engine::Granular,
+        Engine::Drum,
+        Engine::Organ,
+        Engine::Vector,
+    ];
+
+    pub fn from_param(value: f32) -> Engine {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::Subtractive => "Subtractive",
+            Engine::Fm => "FM",
+            Engine::Additive => "Additive",
+            Engine::Pluck => "Pluck",
+            Engine::Sampler => "Sampler",
+            Engine::Granular => "Granular",
+            Engine::Drum => "Drum",
+            Engine::Organ => "Organ",
+            Engine::Vector => "Vector",
+        }
+    }
+}
+
+// How key velocity maps to the 0-1 velocity the voice sees.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VelocityCurve {
+    // Loud notes come easily.
+    Soft,
+    Linear,
+    // Loud notes need a firm hand.
+    Hard,
+    // Every note plays at full velocity.
+    Fixed,
+}
+
+impl VelocityCurve {
+    const ALL: [VelocityCurve; 4] = [
+        VelocityCurve::Soft,
+        VelocityCurve::Linear,
+        VelocityCurve::Hard,
+        VelocityCurve::Fixed,
+    ];
+
+    pub fn from_param(value: f32) -> VelocityCurve {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    // Centre of the parameter range that selects this curve.
+    pub fn to_param(self) -> f32 {
+        let index = Self::ALL.iter().position(|&c| c == self).unwrap_or(0);
+        (index as f32 + 0.5) / Self::ALL.len() as f32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VelocityCurve::Soft => "Soft",
+            VelocityCurve::Linear => "Linear",
+            VelocityCurve::Hard => "Hard",
+            VelocityCurve::Fixed => "Fixed",
+        }
+    }
+
+    fn apply(&self, velocity: f32) -> f32 {
+        match self {
+            VelocityCurve::Soft => velocity.sqrt(),
+            VelocityCurve::Linear => velocity,
+            VelocityCurve::Hard => velocity * velocity,
+            VelocityCurve::Fixed => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VoiceMode {
+    Poly,
+    // One voice, retriggered by every note.
+    Mono,
+    // One voice that glides between overlapping notes without retriggering, whether or
+    // not Glide is on.
+    Legato,
+}
+
+impl VoiceMode {
+    const ALL: [VoiceMode; 3] = [VoiceMode::Poly, VoiceMode::Mono, VoiceMode::Legato];
+
+    pub fn from_param(value: f32) -> VoiceMode {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceMode::Poly => "Poly",
+            VoiceMode::Mono => "Mono",
+            VoiceMode::Legato => "Legato",
+        }
+    }
+}
+
+// Which sounding note makes way when every voice is busy. Notes already released are
+// always taken before held ones.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StealPolicy {
+    Oldest,
+    Quietest,
+    // A voice on the same note number, from another channel, or else the oldest.
+    SameNote,
+    Lowest,
+}
+
+impl StealPolicy {
+    const ALL: [StealPolicy; 4] = [
+        StealPolicy::Oldest,
+        StealPolicy::Quietest,
+        StealPolicy::SameNote,
+        StealPolicy::Lowest,
+    ];
+
+    pub fn from_param(value: f32) -> StealPolicy {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StealPolicy::Oldest => "Oldest",
+            StealPolicy::Quietest => "Quietest",
+            StealPolicy::SameNote => "Same Note",
+            StealPolicy::Lowest => "Lowest",
+        }
+    }
+}
+
+// Which held key a mono voice plays.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NotePriority {
+    Last,
+    Low,
+    High,
+}
+
+impl NotePriority {
+    const ALL: [NotePriority; 3] = [NotePriority::Last, NotePriority::Low, NotePriority::High];
+
+    pub fn from_param(value: f32) -> NotePriority {
+        let index = (value * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotePriority::Last => "Last",
+            NotePriority::Low => "Low",
+            NotePriority::High => "High",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct HeldNote {
+    channel: u8,
+    note: u8,
+    velocity: u8,
+}
+
+// Keys currently down, oldest first, so mono mode can fall back to an earlier key when
+// the one sounding is let go.
+#[derive(Default)]
+struct HeldNotes {
+    notes: [HeldNote; MAX_HELD],
+    len: usize,
+}
+
+impl HeldNotes {
+    fn push(&mut self, held: HeldNote) {
+        self.remove(held.channel, held.note);
+        if self.len == MAX_HELD {
+            self.notes.copy_within(1.., 0);
+            self.len -= 1;
+        }
+        self.notes[self.len] = held;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, channel: u8, note: u8) {
+        let held = &self.notes[..self.len];
+        if let Some(index) = held.iter().position(|h| h.channel == channel && h.note == note) {
+            self.notes.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn choose(&self, priority: NotePriority) -> Option<HeldNote> {
+        let held = self.notes[..self.len].iter().copied();
+        match priority {
+            NotePriority::Last => held.last(),
+            NotePriority::Low => held.min_by_key(|h| h.note),
+            NotePriority::High => held.max_by_key(|h| h.note),
+        }
+    }
+}
+
+// Per-note expression last sent on an MPE member channel. A note starts from its
+// channel's values, since controllers send them just before the note-on.
+#[derive(Clone, Copy, Default)]
+struct ChannelExpression {
+    // Bend from -1 to 1, pressure and timbre from 0 to 1.
+    bend: f32,
+    pressure: f32,
+    timbre: f32,
+}
+
+// What a voice holds between control updates.
+#[derive(Clone, Copy, Default)]
+struct Control {
+    engine: Engine,
+    smoothed: SmoothedParameters,
+    amp_envelope: EnvelopeSettings,
+    filter: FilterSettings,
+    gain: f32,
+    // The sounding frequency, in Hz.
+    freq: f32,
+    oscillators: Oscillators,
+    fm: FmSettings,
+    // Harmonic amplitudes for the additive and organ engines, whichever is playing.
+    harmonics: [f32; MAX_HARMONICS],
+    // Per-sample feedback that gives the plucked string its decay at this pitch.
+    pluck_feedback: f32,
+    // Playback speed against a sample's root note; the sample's own rate scales it.
+    sample_pitch: f32,
+    sample_start: f32,
+    sample_loop: bool,
+    // Everything but the playback rate, which depends on the sample.
+    grains: GrainSettings,
+    // Drum pitch as a ratio, and the multiplier on its decay times.
+    drum_tune: f32,
+    drum_decay: f32,
+    click_level: f32,
+    // What the key click is multiplied by each sample.
+    click_decay: f32,
+}
+
+// The subtractive and vector engines' oscillator settings, as phase increments and
+// gains. Pulse width is left to each sample, since PWM can sweep it at audio rates.
+#[derive(Clone, Copy, Default)]
+struct Oscillators {
+    sync: bool,
+    // Osc1's before unison detune, osc2's and the sub's.
+    inc1: f32,
+    inc2: f32,
+    sub_inc: f32,
+    osc1: OscillatorSettings,
+    osc2: OscillatorSettings,
+    sub: OscillatorSettings,
+    noise: NoiseColor,
+    fm_index: f32,
+    mix: f32,
+    // Each unison copy's detune as a ratio, and its left and right gains.
+    unison: usize,
+    unison_ratios: [f32; MAX_UNISON],
+    unison_gains: [(f32, f32); MAX_UNISON],
+    // The vector slots share osc1's increment.
+    vector: [OscillatorSettings; VECTOR_SLOTS],
+    vector_gains: [f32; VECTOR_SLOTS],
+    pwm_source: PwmSource,
+    pwm_inc: f32,
+    // Pulse width before the PWM source, and how far the source moves it.
+    pulse_width: f32,
+    pwm_depth: f32,
+}
+
+#[derive(Default)]
+pub struct Voice {
+    // MIDI channel from 0 to 15; always 0 outside MPE.
+    channel: u8,
+    note: u8,
+    // The pitch sounding, in semitones; trails `note` after a legato change.
+    glide_note: f32,
+    // Note-on velocity from 0 to 1.
+    velocity: f32,
+    note_on: bool,
+    // Key released while a pedal was holding the note; the note-off waits for the pedal.
+    sustained: bool,
+    // Key was down when the sostenuto pedal went down, so that pedal holds it.
+    sostenuto: bool,
+    active: bool,
+    // Taken for a new note; fading out over `STEAL_FADE` before going idle.
+    stolen: bool,
+    fade: f32,
+    // Organ key click level, set at note-on and dying away on its own.
+    click: f32,
+    // Latest poly key pressure, from 0 to 1, and its smoothed value.
+    pressure: f32,
+    pressure_smoother: Smoother,
+    // Per-note MPE bend, from -1 to 1, and CC74 timbre, from 0 to 1.
+    bend: f32,
+    timbre: f32,
+    // The per-note random mod source, from -1 to 1.
+    random: f32,
+    // Seconds since note-on, for the LFO's onset delay and fade-in.
+    lfo_age: f32,
+    age: u64,
+    osc1_phases: [f32; MAX_UNISON],
+    osc2_phase: f32,
+    vector_phases: [f32; VECTOR_SLOTS],
+    sub_phase: f32,
+    pwm_phase: f32,
+    noise: NoiseGenerator,
+    // Draws the per-note random value, the unison start phases and noise waveforms.
+    rng: Random,
+    fm: FmVoice,
+    string: PluckString,
+    sampler: SamplerVoice,
+    granular: GranularVoice,
+    drum: DrumVoice,
+    drift: Drift,
+    // Frequency ratio from drift for the current sample.
+    drift_pitch: f32,
+    // One filter per output channel.
+    filters: [VoiceFilter; 2],
+    amp_envelope: Envelope,
+    filter_envelope: Envelope,
+    mod_envelope: Envelope,
+    modulation: Modulation,
+    // Set at note-on for engines that need params or the sample rate to initialise.
+    start_pending: bool,
+    control: Control,
+    // Set at note-on so a new voice doesn't wait for the next control block.
+    control_pending: bool,
+}
+
+impl Voice {
+    fn start(&mut self, channel: u8, note: u8, velocity: f32, age: u64) {
+        self.channel = channel;
+        self.note = note;
+        self.glide_note = note as f32;
+        self.velocity = velocity;
+        self.note_on = true;
+        self.sustained = false;
+        self.sostenuto = false;
+        self.active = true;
+        self.stolen = false;
+        self.fade = 1.0;
+        self.click = 1.0;
+        self.pressure_smoother = Smoother::default();
+        self.random = self.rng.bipolar();
+        self.lfo_age = 0.0;
+        self.age = age;
+        // Free-running unison copies start scattered so they don't phase on the attack.
+        self.osc1_phases = std::array::from_fn(|i| if i == 0 { 0.0 } else { self.rng.unit() });
+        self.osc2_phase = 0.0;
+        self.sub_phase = 0.0;
+        self.vector_phases = [0.0; VECTOR_SLOTS];
+        self.fm.reset();
+        self.granular.reset();
+        self.drum.start(note);
+        self.drift.reset();
+        self.filters.iter_mut().for_each(VoiceFilter::reset);
+        self.amp_envelope.trigger();
+        self.filter_envelope.trigger();
+        self.mod_envelope.trigger();
+        self.start_pending = true;
+        self.control_pending = true;
+    }
+
+    // Every envelope the voice owns, whichever engine is playing.
+    fn envelopes(&mut self) -> impl Iterator<Item = &mut Envelope> {
+        [&mut self.amp_envelope, &mut self.filter_envelope, &mut self.mod_envelope]
+            .into_iter()
+            .chain(self.fm.envelopes_mut().iter_mut())
+    }
+
+    fn envelope_snapshot(&mut self) -> [Envelope; ENVELOPES] {
+        let mut snapshot = [Envelope::default(); ENVELOPES];
+        for (slot, envelope) in snapshot.iter_mut().zip(self.envelopes()) {
+            *slot = *envelope;
+        }
+        snapshot
+    }
+
+    fn release(&mut self) {
+        self.note_on = false;
+        self.amp_envelope.release();
+        self.filter_envelope.release();
+        self.mod_envelope.release();
+        self.fm.release();
+    }
+
+    // Everything that changes slowly: drift, glide, the filter and mod envelopes, the
+    // modulation matrix, filter coefficients and gain. `block_time` is how long, in
+    // seconds, the results will be held for.
+    fn update_control(
+        &mut self,
+        params: &RustSynthParameters,
+        per_sample: f32,
+        shared: &SharedSources,
+        smoothed: &SmoothedParameters,
+        block_time: f32,
+    ) {
+        let (pitch, level) = self.drift.next(params.drift.get(), block_time);
+        self.drift_pitch = pitch;
+        self.glide(params, block_time);
+
+        let depth = self.velocity_depth(params);
+        let attack_scale = self.velocity_attack_scale(params);
+
+        let mut amp_settings = params.amp_envelope();
+        amp_settings.attack *= attack_scale;
+        let mut filter_settings = params.filter_envelope();
+        filter_settings.attack *= attack_scale;
+        let filter_level = self.filter_envelope.next(&filter_settings, block_time) * depth;
+        let mut mod_settings = params.mod_envelope();
+        mod_settings.attack *= attack_scale;
+        let mod_level = self.mod_envelope.next(&mod_settings, block_time) * depth;
+        let lfo = shared.lfo * self.lfo_onset(params, block_time);
+
+        self.modulation.clear();
+        self.modulation.add(params.mod_env_destination(), mod_level * params.mod_env_depth());
+        let mod_wheel = shared.mod_wheel * params.mod_wheel_depth();
+        self.modulation.add(params.mod_wheel_destination(), mod_wheel);
+        // Pitch modulation is scaled to ±24 semitones.
+        let bend = shared.pitch_bend * params.bend_range_semitones() as f32;
+        self.modulation.add(Destination::Pitch, bend / 24.0);
+        self.modulation.add(Destination::Pitch, self.bend * MPE_BEND_RANGE / 24.0);
+        let sample_hold = shared.sample_hold * params.sample_hold_depth();
+        self.modulation.add(params.sample_hold_destination(), sample_hold);
+        let pressure = self.pressure_smoother.next(self.pressure, PRESSURE_SMOOTHING, block_time);
+        let sources = SourceValues {
+            shared: SharedSources { lfo, ..*shared },
+            amp_envelope: self.amp_envelope.level(),
+            filter_envelope: filter_level,
+            mod_envelope: mod_level,
+            velocity: self.velocity,
+            note: self.note,
+            pressure,
+            timbre: self.timbre,
+            random: self.random,
+        };
+        self.modulation.add_slots(&params.mod_slots, &sources);
+        self.modulation.add_macros(&params.macros);
+        // Last, so everything routed to LFO Depth has been summed.
+        let vibrato = params.lfo_depth_semitones(self.modulation.get(Destination::LfoDepth));
+        self.modulation.add(Destination::Pitch, lfo * vibrato / 24.0);
+
+        // Cutoff is set for middle C and follows the note from there.
+        let tracking = (self.freq() / midi_note_to_freq(60.0)).log2() * params.key_track_amount();
+        let modulation = self.modulation.get(Destination::Cutoff) * 8.0;
+        let sweep = filter_level * params.filter_env_octaves() + tracking + modulation;
+        let cutoff = (smoothed.cutoff_hz * sweep.exp2()).clamp(20.0, 20000.0);
+        let filter = FilterSettings::new(
+            FilterType::from_param(params.filter_type.get()),
+            cutoff,
+            smoothed.resonance,
+            smoothed.drive_gain,
+            params.vowel.get(),
+            self.freq() * (params.comb_tune_semitones() / 12.0).exp2(),
+            per_sample,
+        );
+
+        let engine = Engine::from_param(params.engine.get());
+        let freq = self.freq();
+        let mut harmonics = [0.0; MAX_HARMONICS];
+        match engine {
+            Engine::Pluck => self.string.tune(freq, 1.0 / per_sample),
+            Engine::Additive => harmonics = params.additive_amplitudes(),
+            Engine::Organ => {
+                let organ = organ::amplitudes(&params.drawbar_levels());
+                harmonics[..organ::HIGHEST_HARMONIC].copy_from_slice(&organ);
+            }
+            _ => (),
+        }
+
+        let gain = level * depth * (1.0 + self.modulation.get(Destination::Amp)).max(0.0);
+        let sample_pitch = ((self.glide_note - ROOT_NOTE as f32) / 12.0).exp2()
+            * self.drift_pitch
+            * self.pitch_modulation();
+        let drum_tune = (params.drum_tune_semitones() / 12.0).exp2()
+            * self.drift_pitch
+            * self.pitch_modulation();
+        self.control = Control {
+            engine,
+            smoothed: *smoothed,
+            amp_envelope: amp_settings,
+            filter,
+            gain,
+            freq,
+            oscillators: self.oscillators(params, smoothed, freq, per_sample),
+            fm: FmSettings::new(params, freq, per_sample),
+            harmonics,
+            pluck_feedback: pluck::feedback_for_decay(freq, params.pluck_decay_seconds()),
+            sample_pitch,
+            sample_start: params.sample_start.get(),
+            sample_loop: params.sample_loop.get() >= 0.5,
+            grains: GrainSettings {
+                size_seconds: params.grain_size_seconds(),
+                density_hz: params.grain_density_hz(),
+                position: params.grain_position.get(),
+                jitter_semitones: params.grain_jitter_semitones(),
+                rate: 0.0,
+            },
+            drum_tune,
+            drum_decay: params.drum_decay_scale(),
+            click_level: params.key_click.get(),
+            click_decay: (-per_sample / 0.002).exp(),
+        };
+        self.control_pending = false;
+    }
+
+    fn oscillators(
+        &self,
+        params: &RustSynthParameters,
+        smoothed: &SmoothedParameters,
+        freq: f32,
+        per_sample: f32,
+    ) -> Oscillators {
+        let sync = params.sync_enabled();
+        let freq1 = if sync {
+            freq * (params.sync_tune_semitones() / 12.0).exp2()
+        } else {
+            freq
+        };
+        let freq2 = freq * params.fm_ratio() * (params.osc2_detune() / 12.0).exp2();
+        let sub_freq = freq / (params.sub_octaves() as f32).exp2();
+
+        let modulation = &self.modulation;
+        let osc1 = oscillator_settings(params, modulation, params.waveform.get());
+        let osc2 = oscillator_settings(params, modulation, params.osc2_waveform.get());
+        let sub = OscillatorSettings {
+            waveform: SubShape::from_param(params.sub_shape.get()).waveform(),
+            ..osc1
+        };
+        let mix = (smoothed.osc_mix + modulation.get(Destination::OscMix)).clamp(0.0, 1.0);
+
+        // Osc1 is a stack of `unison` copies spread symmetrically in pitch and pan.
+        let unison = params.unison_voices();
+        let detune = params.unison_detune_cents();
+        let spread = params.stereo_spread.get();
+        let gain = 1.0 / (unison as f32).sqrt();
+        let mut unison_ratios = [1.0; MAX_UNISON];
+        let mut unison_gains = [(0.0, 0.0); MAX_UNISON];
+        for i in 0..unison {
+            let position = if unison > 1 {
+                i as f32 / (unison - 1) as f32 * 2.0 - 1.0
+            } else {
+                0.0
+            };
+            unison_ratios[i] = (position * detune / 1200.0).exp2();
+            let pan = position * spread;
+            unison_gains[i] = (gain * (1.0 - pan).min(1.0), gain * (1.0 + pan).min(1.0));
+        }
+
+        // Four slots at the corners of the X/Y square (A bottom-left, B bottom-right,
+        // C top-left, D top-right), mixed with equal-power gains.
+        let x = params.vector_x.get() + modulation.get(Destination::VectorX);
+        let y = params.vector_y.get() + modulation.get(Destination::VectorY);
+        let x = x.clamp(0.0, 1.0) * PI / 2.0;
+        let y = y.clamp(0.0, 1.0) * PI / 2.0;
+        let (left, right) = (x.cos(), x.sin());
+        let (bottom, top) = (y.cos(), y.sin());
+        let vector = std::array::from_fn(|slot| {
+            oscillator_settings(params, modulation, params.vector_waves[slot].get())
+        });
+
+        Oscillators {
+            sync,
+            inc1: freq1 * per_sample,
+            inc2: freq2 * per_sample,
+            sub_inc: sub_freq * per_sample,
+            osc1,
+            osc2,
+            sub,
+            noise: NoiseColor::from_param(params.noise_color.get()),
+            fm_index: (params.fm_index() + modulation.get(Destination::FmAmount) * 10.0).max(0.0),
+            mix,
+            unison,
+            unison_ratios,
+            unison_gains,
+            vector,
+            vector_gains: [left * bottom, right * bottom, left * top, right * top],
+            pwm_source: PwmSource::from_param(params.pwm_source.get()),
+            pwm_inc: params.pwm_rate_hz() * per_sample,
+            pulse_width: params.pulse_width_fraction()
+                + modulation.get(Destination::PulseWidth) * 0.45,
+            pwm_depth: params.pwm_depth.get() * 0.45,
+        }
+    }
+
+    // One sample, from the settings the last control update left. `block_time` is given
+    // on the first sample of each control block.
+    fn render(
+        &mut self,
+        params: &RustSynthParameters,
+        per_sample: f32,
+        shared: &SharedSources,
+        smoothed: &SmoothedParameters,
+        block_time: Option<f32>,
+        sample: Option<&Sample>,
+    ) -> (f32, f32) {
+        // A voice started partway through a block catches up straight away.
+        if let Some(block_time) = block_time.or(self.control_pending.then_some(per_sample)) {
+            self.update_control(params, per_sample, shared, smoothed, block_time);
+        }
+        let engine = self.control.engine;
+        let envelope = self.amp_envelope.next(&self.control.amp_envelope, per_sample);
+        let freq = self.control.freq;
+
+        let (left, right) = match engine {
+            Engine::Subtractive => {
+                let (left, right) = self.generate_wave(envelope);
+                (left * envelope, right * envelope)
+            }
+            Engine::Fm => {
+                let out = self.fm.render(&self.control.fm, per_sample);
+                (out, out)
+            }
+            Engine::Additive => {
+                let phase_inc = freq * per_sample;
+                let phase = &mut self.osc1_phases[0];
+                let out = additive::render(*phase, phase_inc, &self.control.harmonics) * envelope;
+                *phase = (*phase + phase_inc).fract();
+                (out, out)
+            }
+            Engine::Pluck => {
+                if self.start_pending {
+                    self.string.excite(params.pluck_brightness.get());
+                    self.start_pending = false;
+                }
+                let out = self.string.next(self.control.pluck_feedback) * envelope;
+                (out, out)
+            }
+            Engine::Sampler => match sample {
+                Some(sample) => {
+                    let start = self.control.sample_start;
+                    if self.start_pending {
+                        self.sampler.start(sample, start);
+                        self.start_pending = false;
+                    }
+                    let rate = self.control.sample_pitch * sample.sample_rate * per_sample;
+                    let looping = self.control.sample_loop;
+                    let (left, right) = self.sampler.next(sample, rate as f64, start, looping);
+                    (left * envelope, right * envelope)
+                }
+                None => (0.0, 0.0),
+            },
+            Engine::Granular => match sample {
+                Some(sample) => {
+                    let settings = GrainSettings {
+                        rate: self.control.sample_pitch * sample.sample_rate * per_sample,
+                        ..self.control.grains
+                    };
+                    let (left, right) = self.granular.next(sample, &settings, 1.0 / per_sample);
+                    (left * envelope, right * envelope)
+                }
+                None => (0.0, 0.0),
+            },
+            Engine::Drum => {
+                let (tune, decay) = (self.control.drum_tune, self.control.drum_decay);
+                let out = self.drum.next(per_sample, tune, decay);
+                (out, out)
+            }
+            Engine::Organ => {
+                let phase_inc = freq * 0.5 * per_sample;
+                let amplitudes = &self.control.harmonics[..organ::HIGHEST_HARMONIC];
+                let phase = &mut self.osc1_phases[0];
+                let tone = additive::render(*phase, phase_inc, amplitudes);
+                *phase = (*phase + phase_inc).fract();
+
+                let click_level = self.control.click_level * self.click;
+                let click = self.noise.next(NoiseColor::White) * click_level;
+                self.click *= self.control.click_decay;
+                let out = (tone + click) * envelope;
+                (out, out)
+            }
+            Engine::Vector => {
+                let out = self.vector_wave(envelope) * envelope;
+                (out, out)
+            }
+        };
+
+        let left = self.filters[0].process(left, &self.control.filter);
+        let right = self.filters[1].process(right, &self.control.filter);
+
+        let finished = match engine {
+            Engine::Fm => self.fm.finished(),
+            // One-shot: note-off doesn't end the hit.
+            Engine::Drum => self.drum.finished(self.control.drum_decay),
+            _ => self.amp_envelope.is_idle(),
+        };
+        let gain = self.control.gain * self.fade;
+        if self.stolen {
+            self.fade -= per_sample / STEAL_FADE;
+        }
+        if finished || self.fade <= 0.0 {
+            self.active = false;
+        }
+        (left * gain, right * gain)
+    }
+
+    fn generate_wave(&mut self, envelope: f32) -> (f32, f32) {
+        let pulse_width = self.pulse_width(envelope);
+        let oscillators = &self.control.oscillators;
+        let (inc1, inc2, sub_inc) = (oscillators.inc1, oscillators.inc2, oscillators.sub_inc);
+        let osc1 = OscillatorSettings { pulse_width, ..oscillators.osc1 };
+        let osc2 = OscillatorSettings { pulse_width, ..oscillators.osc2 };
+        let sub = OscillatorSettings { pulse_width, ..oscillators.sub };
+        let wave2 = osc2.sample(self.osc2_phase, inc2, &mut self.rng);
+        let fm_offset = wave2 * oscillators.fm_index / (2.0 * PI);
+        let sub_wave = sub.sample(self.sub_phase, sub_inc, &mut self.rng);
+        let noise = self.noise.next(oscillators.noise);
+
+        let voices = oscillators.unison;
+        let mut unison_incs = [inc1; MAX_UNISON];
+        let (mut left, mut right) = (0.0, 0.0);
+        for (i, phase) in self.osc1_phases.iter_mut().take(voices).enumerate() {
+            let inc = inc1 * oscillators.unison_ratios[i];
+            let wave = osc1.sample((*phase + fm_offset).rem_euclid(1.0), inc, &mut self.rng);
+            let (left_gain, right_gain) = oscillators.unison_gains[i];
+            left += wave * left_gain;
+            right += wave * right_gain;
+
+            *phase = (*phase + inc).fract();
+            unison_incs[i] = inc;
+        }
+
+        self.sub_phase = (self.sub_phase + sub_inc).fract();
+        self.osc2_phase += inc2;
+        if self.osc2_phase >= 1.0 {
+            self.osc2_phase -= 1.0;
+            if oscillators.sync {
+                // Restart osc1 where it would be had it reset exactly at osc2's wrap.
+                for (phase, inc) in self.osc1_phases.iter_mut().zip(unison_incs).take(voices) {
+                    *phase = (self.osc2_phase * inc / inc2).fract();
+                }
+            }
+        }
+
+        let smoothed = &self.control.smoothed;
+        let mix = oscillators.mix;
+        let ring = smoothed.ring_mod;
+        let extra = sub_wave * smoothed.sub_level + noise * smoothed.noise_level;
+        let blend = |osc1: f32| {
+            let oscillators = osc1 * (1.0 - mix) + wave2 * mix;
+            oscillators * (1.0 - ring) + osc1 * wave2 * ring + extra
+        };
+        (blend(left), blend(right))
+    }
+
+    fn vector_wave(&mut self, envelope: f32) -> f32 {
+        let pulse_width = self.pulse_width(envelope);
+        let oscillators = &self.control.oscillators;
+        let phase_inc = oscillators.inc1;
+        let mut out = 0.0;
+        for (slot, phase) in self.vector_phases.iter_mut().enumerate() {
+            let settings = OscillatorSettings { pulse_width, ..oscillators.vector[slot] };
+            let wave = settings.sample(*phase, phase_inc, &mut self.rng);
+            out += wave * oscillators.vector_gains[slot];
+            *phase = (*phase + phase_inc).fract();
+        }
+        out
+    }
+
+    fn pulse_width(&mut self, envelope: f32) -> f32 {
+        let oscillators = &self.control.oscillators;
+        let modulation = match oscillators.pwm_source {
+            PwmSource::Lfo => {
+                self.pwm_phase = (self.pwm_phase + oscillators.pwm_inc).fract();
+                1.0 - 4.0 * ((self.pwm_phase + 0.25).fract() - 0.5).abs()
+            }
+            PwmSource::Envelope => envelope,
+        };
+        let width = oscillators.pulse_width + modulation * oscillators.pwm_depth;
+        width.clamp(0.05, 0.95)
+    }
+
+    // Slides the sounding pitch toward the key, with Glide Time as the time constant, over
+    // `seconds`.
+    fn glide(&mut self, params: &RustSynthParameters, seconds: f32) {
+        let target = self.note as f32;
+        let time = params.glide_seconds();
+        if time <= seconds {
+            self.glide_note = target;
+        } else {
+            self.glide_note += (target - self.glide_note) * (1.0 - (-seconds / time).exp());
+        }
+    }
+
+    // How much of the LFO reaches this voice: none until the delay has passed, then
+    // rising linearly over the fade-in. Ages the voice by `seconds`.
+    fn lfo_onset(&mut self, params: &RustSynthParameters, seconds: f32) -> f32 {
+        let since_delay = self.lfo_age - params.lfo_delay_seconds();
+        self.lfo_age += seconds;
+        if since_delay < 0.0 {
+            0.0
+        } else {
+            (since_delay / params.lfo_fade_seconds().max(seconds)).min(1.0)
+        }
+    }
+
+    // How much of each envelope's full depth this note gets; soft notes lose up to all
+    // of it.
+    fn velocity_depth(&self, params: &RustSynthParameters) -> f32 {
+        1.0 - params.velocity_to_envelope.get() * (1.0 - self.velocity)
+    }
+
+    // Hard notes get attacks down to a tenth as long.
+    fn velocity_attack_scale(&self, params: &RustSynthParameters) -> f32 {
+        1.0 - params.velocity_to_attack.get() * self.velocity * 0.9
+    }
+
+    fn steal(&mut self) {
+        self.note_on = false;
+        self.sustained = false;
+        self.sostenuto = false;
+        self.stolen = true;
+    }
+
+    fn plays(&self, channel: u8, note: u8) -> bool {
+        self.channel == channel && self.note == note
+    }
+
+    fn freq(&self) -> f32 {
+        midi_note_to_freq(self.glide_note) * self.drift_pitch * self.pitch_modulation()
+    }
+
+    // Frequency ratio from modulation routed to pitch.
+    fn pitch_modulation(&self) -> f32 {
+        (self.modulation.get(Destination::Pitch) * 2.0).exp2()
+    }
+}
+
+pub struct VoiceManager {
+    voices: [Voice; MAX_VOICES + STEAL_HEADROOM],
+    next_age: u64,
+    sample: Option<Arc<Sample>>,
+    channels: [ChannelExpression; MIDI_CHANNELS],
+    sustain_pedal: bool,
+    sostenuto_pedal: bool,
+    held: HeldNotes,
+}
+
+impl Default for VoiceManager {
+    // Written out because `Default` isn't derived for arrays this long.
+    fn default() -> VoiceManager {
+        VoiceManager {
+            voices: std::array::from_fn(|_| Voice::default()),
+            next_age: 0,
+            sample: None,
+            channels: [ChannelExpression::default(); MIDI_CHANNELS],
+            sustain_pedal: false,
+            sostenuto_pedal: false,
+            held: HeldNotes::default(),
+        }
+    }
+}
+
+impl VoiceManager {
+    pub fn set_sample(&mut self, sample: Option<Arc<Sample>>) {
+        self.sample = sample;
+    }
+
+    pub fn note_on(&mut self, params: &RustSynthParameters, channel: u8, note: u8, velocity: u8) {
+        let held = HeldNote { channel, note, velocity };
+        self.held.push(held);
+        if params.voice_mode() == VoiceMode::Poly {
+            self.start_note(params, held);
+            return;
+        }
+        let priority = NotePriority::from_param(params.note_priority.get());
+        if let Some(chosen) = self.held.choose(priority) {
+            self.play_mono(params, chosen);
+        }
+    }
+
+    fn play_mono(&mut self, params: &RustSynthParameters, held: HeldNote) {
+        let voice = &mut self.voices[0];
+        if voice.note_on && voice.plays(held.channel, held.note) {
+            return;
+        }
+        if params.voice_mode() == VoiceMode::Legato && voice.note_on {
+            // A key was already down: slide over without restarting anything.
+            voice.channel = held.channel;
+            voice.note = held.note;
+            voice.sustained = false;
+        } else {
+            self.start_note(params, held);
+        }
+    }
+
+    fn start_note(&mut self, params: &RustSynthParameters, held: HeldNote) {
+        let HeldNote { channel, note, velocity } = held;
+        let age = self.next_age;
+        self.next_age += 1;
+
+        // In Mono and Legato a note is legato when another key is still down; it takes
+        // over the envelopes of the most recently played one. Poly notes each start their
+        // own.
+        let mono = params.voice_mode() != VoiceMode::Poly;
+        let previous = (self.voices.iter_mut())
+            .filter(|voice| mono && voice.note_on && !voice.plays(channel, note))
+            .max_by_key(|voice| voice.age)
+            .map(Voice::envelope_snapshot);
+        // With Glide on, every note slides in from wherever the last one had got to.
+        let glide_from = self
+            .voices
+            .iter()
+            .max_by_key(|voice| voice.age)
+            .filter(|_| age > 0 && params.glide_enabled())
+            .map(|voice| voice.glide_note);
+
+        let expression = self.channels[channel as usize];
+        let voice = if mono {
+            &mut self.voices[0]
+        } else {
+            self.allocate(params, channel, note)
+        };
+        let curve = VelocityCurve::from_param(params.velocity_curve.get());
+        voice.start(channel, note, curve.apply(velocity as f32 / 127.0), age);
+        if let Some(from) = glide_from {
+            voice.glide_note = from;
+        }
+        voice.bend = expression.bend;
+        voice.pressure = expression.pressure;
+        voice.timbre = expression.timbre;
+        match (Retrigger::from_param(params.retrigger.get()), previous) {
+            (_, _) if !mono => (),
+            (Retrigger::Zero, _) => {
+                voice.envelopes().for_each(|envelope| envelope.trigger_from(0.0));
+            }
+            (Retrigger::Current, Some(previous)) => {
+                for (envelope, previous) in voice.envelopes().zip(previous) {
+                    envelope.trigger_from(previous.level());
+                }
+            }
+            (Retrigger::Legato, Some(previous)) => {
+                for (envelope, previous) in voice.envelopes().zip(previous) {
+                    *envelope = previous;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn poly_pressure(&mut self, channel: u8, note: u8, pressure: f32) {
+        for voice in self.voices.iter_mut() {
+            if voice.note_on && voice.plays(channel, note) {
+                voice.pressure = pressure;
+            }
+        }
+    }
+
+    pub fn channel_bend(&mut self, channel: u8, bend: f32) {
+        self.channels[channel as usize].bend = bend;
+        self.held_on(channel).for_each(|voice| voice.bend = bend);
+    }
+
+    pub fn channel_pressure(&mut self, channel: u8, pressure: f32) {
+        self.channels[channel as usize].pressure = pressure;
+        self.held_on(channel).for_each(|voice| voice.pressure = pressure);
+    }
+
+    pub fn channel_timbre(&mut self, channel: u8, timbre: f32) {
+        self.channels[channel as usize].timbre = timbre;
+        self.held_on(channel).for_each(|voice| voice.timbre = timbre);
+    }
+
+    pub fn note_off(&mut self, params: &RustSynthParameters, channel: u8, note: u8) {
+        self.held.remove(channel, note);
+        if params.voice_mode() != VoiceMode::Poly {
+            // Fall back to the key that now has priority, if any is still down.
+            let priority = NotePriority::from_param(params.note_priority.get());
+            if let Some(chosen) = self.held.choose(priority) {
+                self.play_mono(params, chosen);
+                return;
+            }
+        }
+        let sustain = self.sustain_pedal;
+        for voice in self.voices.iter_mut() {
+            if voice.note_on && voice.plays(channel, note) {
+                if sustain || voice.sostenuto {
+                    voice.sustained = true;
+                } else {
+                    voice.release();
+                }
+            }
+        }
+    }
+
+    // Releases every note, including any a pedal is holding.
+    pub fn all_notes_off(&mut self) {
+        self.held.clear();
+        for voice in self.voices.iter_mut().filter(|voice| voice.note_on) {
+            voice.sustained = false;
+            voice.sostenuto = false;
+            voice.release();
+        }
+    }
+
+    // Silences everything at once, release tails included.
+    pub fn all_sound_off(&mut self) {
+        self.held.clear();
+        for voice in self.voices.iter_mut() {
+            voice.note_on = false;
+            voice.sustained = false;
+            voice.sostenuto = false;
+            voice.active = false;
+        }
+    }
+
+    pub fn set_sustain(&mut self, down: bool) {
+        self.sustain_pedal = down;
+        self.release_deferred();
+    }
+
+    // Only notes whose keys are down at this moment are held; later ones play normally.
+    pub fn set_sostenuto(&mut self, down: bool) {
+        // Pedals resend their position; only the press itself picks the notes.
+        if down == self.sostenuto_pedal {
+            return;
+        }
+        self.sostenuto_pedal = down;
+        for voice in self.voices.iter_mut() {
+            voice.sostenuto = down && voice.note_on && !voice.sustained;
+        }
+        self.release_deferred();
+    }
+
+    // Sends the held-back note-offs of any note no pedal is holding any more.
+    fn release_deferred(&mut self) {
+        let sustain = self.sustain_pedal;
+        for voice in self.voices.iter_mut() {
+            if voice.sustained && !sustain && !voice.sostenuto {
+                voice.sustained = false;
+                voice.release();
+            }
+        }
+    }
+
+    fn held_on(&mut self, channel: u8) -> impl Iterator<Item = &mut Voice> {
+        self.voices
+            .iter_mut()
+            .filter(move |voice| voice.note_on && voice.channel == channel)
+    }
+
+    pub fn render(
+        &mut self,
+        params: &RustSynthParameters,
+        per_sample: f32,
+        shared: &SharedSources,
+        smoothed: &SmoothedParameters,
+        block_time: Option<f32>,
+    ) -> (f32, f32) {
+        let sample = self.sample.as_deref();
+        self.voices
+            .iter_mut()
+            .filter(|voice| voice.active)
+            .map(|voice| voice.render(params, per_sample, shared, smoothed, block_time, sample))
+            .fold((0.0, 0.0), |(left, right), (l, r)| (left + l, right + r))
+    }
+
+    // Steals voices until no more than Max Voices are sounding, so lowering the limit
+    // with notes held thins them out without clicks.
+    pub fn enforce_limit(&mut self, params: &RustSynthParameters) {
+        let policy = StealPolicy::from_param(params.steal_policy.get());
+        while self.sounding() > params.max_voices() {
+            let victim = self.victim(None, policy);
+            self.voices[victim].steal();
+        }
+    }
+
+    fn sounding(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.active && !voice.stolen).count()
+    }
+
+    // Retrigger a voice already playing this note, otherwise take a free voice. Once
+    // Max Voices are sounding, one is also stolen by the steal policy and left to fade.
+    fn allocate(&mut self, params: &RustSynthParameters, channel: u8, note: u8) -> &mut Voice {
+        let policy = StealPolicy::from_param(params.steal_policy.get());
+        if let Some(index) = self
+            .voices
+            .iter()
+            .position(|voice| voice.active && !voice.stolen && voice.plays(channel, note))
+        {
+            return &mut self.voices[index];
+        }
+
+        let victim = if self.sounding() >= params.max_voices() {
+            let victim = self.victim(Some(note), policy);
+            self.voices[victim].steal();
+            Some(victim)
+        } else {
+            None
+        };
+        // Fall back on the victim's own slot if the headroom is taken by earlier fades.
+        let index = self
+            .voices
+            .iter()
+            .position(|voice| !voice.active)
+            .or(victim)
+            .unwrap_or_else(|| self.victim(Some(note), policy));
+        &mut self.voices[index]
+    }
+
+    // `note` is the one about to start, if any.
+    fn victim(&self, note: Option<u8>, policy: StealPolicy) -> usize {
+        let sounding = |voice: &Voice| voice.active && !voice.stolen;
+        let released = self.voices.iter().any(|voice| sounding(voice) && !voice.note_on);
+        let candidates = || {
+            self.voices
+                .iter()
+                .enumerate()
+                .filter(move |(_, voice)| sounding(voice) && (!released || !voice.note_on))
+        };
+        let oldest = || candidates().min_by_key(|(_, voice)| voice.age);
+        let chosen = match policy {
+            StealPolicy::Oldest => oldest(),
+            StealPolicy::Quietest => candidates().min_by(|(_, a), (_, b)| {
+                a.amp_envelope.level().total_cmp(&b.amp_envelope.level())
+            }),
+            StealPolicy::SameNote => {
+                candidates().find(|(_, voice)| Some(voice.note) == note).or_else(oldest)
+            }
+            StealPolicy::Lowest => candidates().min_by_key(|(_, voice)| voice.note),
+        };
+        chosen.map(|(index, _)| index).unwrap_or(0)
+    }
+}
+
+// Everything but the pulse width, which `pulse_width` gives each sample.
+fn oscillator_settings(
+    params: &RustSynthParameters,
+    modulation: &Modulation,
+    waveform: f32,
+) -> OscillatorSettings {
+    OscillatorSettings {
+        waveform: Waveform::from_param(waveform),
+        pulse_width: 0.5,
+        pd_amount: params.pd_amount.get(),
+        wt_position: params.wt_position.get() + modulation.get(Destination::WtPosition),
+        wt_interpolation: Interpolation::from_param(params.wt_interpolation.get()),
+    }
+}
+
+
+fn midi_note_to_freq(note: f32) -> f32 {
+    const A4_FREQ: f32 = 440.0;
+    const A4_NOTE: f32 = 69.0;
+    ((note - A4_NOTE) / 12.0).exp2() * A4_FREQ
+}
@@ -0,0 +1,164 @@
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+const TABLE_SIZE: usize = 2048;
+
+// Highest harmonic kept in the lowest mip level; each level above halves it, so the
+// last level holds only the fundamental.
+const MAX_HARMONICS: usize = 512;
+const MIP_LEVELS: usize = 10;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Cubic,
+}
+
+impl Interpolation {
+    pub fn from_param(value: f32) -> Interpolation {
+        if value < 0.5 {
+            Interpolation::Linear
+        } else {
+            Interpolation::Cubic
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::Cubic => "Cubic",
+        }
+    }
+}
+
+// One single-cycle waveform, stored once per mip level with the harmonics that would
+// alias at that level's pitch range removed.
+struct Frame {
+    levels: Vec<Vec<f32>>,
+}
+
+impl Frame {
+    fn from_harmonics(amplitudes: &[f32], sine: &[f32]) -> Frame {
+        let levels = (0..MIP_LEVELS)
+            .map(|level| {
+                let harmonics = (MAX_HARMONICS >> level).min(amplitudes.len());
+                let mut table = vec![0.0; TABLE_SIZE];
+                for (k, &amplitude) in amplitudes.iter().take(harmonics).enumerate() {
+                    if amplitude == 0.0 {
+                        continue;
+                    }
+                    let k = k + 1;
+                    for (i, sample) in table.iter_mut().enumerate() {
+                        *sample += amplitude * sine[(k * i) % TABLE_SIZE];
+                    }
+                }
+                normalize(&mut table);
+                table
+            })
+            .collect();
+        Frame { levels }
+    }
+}
+
+pub struct Wavetable {
+    frames: Vec<Frame>,
+}
+
+impl Wavetable {
+    // Each entry of `spectra` is one frame, given as amplitudes of harmonics 1, 2, 3...
+    pub fn from_spectra(spectra: &[Vec<f32>]) -> Wavetable {
+        let sine: Vec<f32> = (0..TABLE_SIZE)
+            .map(|i| (i as f32 / TABLE_SIZE as f32 * 2.0 * PI).sin())
+            .collect();
+        let frames = spectra
+            .iter()
+            .map(|amplitudes| Frame::from_harmonics(amplitudes, &sine))
+            .collect();
+        Wavetable { frames }
+    }
+
+    // `position` morphs across the frames (0–1); the mip level is picked from
+    // `phase_inc` so no harmonic ends up above Nyquist.
+    pub fn sample(
+        &self,
+        phase: f32,
+        phase_inc: f32,
+        position: f32,
+        interpolation: Interpolation,
+    ) -> f32 {
+        let level = mip_level(phase_inc);
+        let scaled = position.clamp(0.0, 1.0) * (self.frames.len() - 1) as f32;
+        let index = (scaled as usize).min(self.frames.len() - 1);
+        let next = (index + 1).min(self.frames.len() - 1);
+        let mix = scaled - index as f32;
+
+        let a = read(&self.frames[index].levels[level], phase, interpolation);
+        if mix == 0.0 {
+            return a;
+        }
+        let b = read(&self.frames[next].levels[level], phase, interpolation);
+        a + (b - a) * mix
+    }
+}
+
+pub fn builtin() -> &'static Wavetable {
+    static BUILTIN: OnceLock<Wavetable> = OnceLock::new();
+    BUILTIN.get_or_init(|| {
+        Wavetable::from_spectra(&[
+            spectrum(|k| if k == 1 { 1.0 } else { 0.0 }),
+            spectrum(|k| if k <= 3 { 1.0 / (k * k) as f32 } else { 0.0 }),
+            spectrum(|k| match k % 4 {
+                1 => 1.0 / (k * k) as f32,
+                3 => -1.0 / (k * k) as f32,
+                _ => 0.0,
+            }),
+            spectrum(|k| 1.0 / k as f32),
+            spectrum(|k| if k % 2 == 1 { 1.0 / k as f32 } else { 0.0 }),
+            spectrum(|k| (PI * k as f32 * 0.25).sin() / k as f32),
+            spectrum(|k| (-((k as f32 - 8.0) / 3.0).powi(2)).exp() + 0.5 / k as f32),
+            spectrum(|k| 1.0 / (k as f32).sqrt()),
+        ])
+    })
+}
+
+fn spectrum(amplitude: impl Fn(usize) -> f32) -> Vec<f32> {
+    (1..=MAX_HARMONICS).map(amplitude).collect()
+}
+
+fn mip_level(phase_inc: f32) -> usize {
+    let allowed = 0.5 / phase_inc.max(f32::EPSILON);
+    (0..MIP_LEVELS)
+        .find(|&level| (MAX_HARMONICS >> level) as f32 <= allowed)
+        .unwrap_or(MIP_LEVELS - 1)
+}
+
+fn read(table: &[f32], phase: f32, interpolation: Interpolation) -> f32 {
+    let position = phase * TABLE_SIZE as f32;
+    let i = (position as usize) % TABLE_SIZE;
+    let frac = position - position.floor();
+    let at = |offset: usize| table[(i + offset) % TABLE_SIZE];
+
+    match interpolation {
+        Interpolation::Linear => {
+            let (y0, y1) = (at(0), at(1));
+            y0 + (y1 - y0) * frac
+        }
+        Interpolation::Cubic => {
+            let (ym1, y0, y1, y2) = (at(TABLE_SIZE - 1), at(0), at(1), at(2));
+            let c1 = 0.5 * (y1 - ym1);
+            let c2 = ym1 - 2.5 * y0 + 2.0 * y1 - 0.5 * y2;
+            let c3 = 0.5 * (y2 - ym1) + 1.5 * (y0 - y1);
+            ((c3 * frac + c2) * frac + c1) * frac + y0
+        }
+    }
+}
+
+fn normalize(table: &mut [f32]) {
+    let peak = table.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    if peak > 0.0 {
+        for sample in table.iter_mut() {
+            *sample /= peak;
+        }
+    }
+}